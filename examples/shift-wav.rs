@@ -7,13 +7,18 @@ use pitch_shift::PitchShifter;
 
 use pico_args::Arguments;
 
-const USAGE: &'static str = r#"usage:
+#[cfg(feature = "g711")]
+#[path = "shift-wav/g711.rs"]
+mod g711;
+
+const USAGE: &str = r#"usage:
     shift-wav -i INPUT_FILE -o OUTPUT_FILE -s SEMITONES
 
 for example, to shift the pitch of my-sample.wav down by one octave:
     shift-wav -i my-sample.wav -o shifted.wav -s -12
 
-note: SEMITONES will be read as a floating point value"#;
+note: SEMITONES will be read as a floating point value
+note: mu-law & A-law input requires building with `--features g711`"#;
 
 fn parse_args(args: &mut Arguments) -> Option<(String, String, f32)> {
     let input_file  = args.value_from_str("-i").ok()?;
@@ -39,6 +44,11 @@ fn main() {
 }
 
 fn read_wav(path: &str) -> (Vec<f32>, usize) {
+    #[cfg(feature = "g711")]
+    if let Some(decoded) = g711::read_companded(path) {
+        return decoded;
+    }
+
     let mut reader = WavReader::open(path).unwrap();
     let spec = reader.spec();
     assert!(spec.sample_format == Int);
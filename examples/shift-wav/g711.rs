@@ -0,0 +1,76 @@
+//! Decoding of mu-law & A-law (G.711) WAV files, which `hound`
+//! doesn't support. Only built with the `g711` feature.
+
+use std::fs::read;
+
+const FORMAT_ALAW: u16 = 6;
+const FORMAT_MULAW: u16 = 7;
+
+/// Returns the first channel of a companded WAV file as linear
+/// samples on the 16-bit scale, along with its sample rate, or
+/// `None` if the file isn't a mu-law / A-law WAV file.
+pub fn read_companded(path: &str) -> Option<(Vec<f32>, usize)> {
+    let bytes = read(path).ok()?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..(offset + 4)];
+        let len = u32_at(offset + 4) as usize;
+        let body = offset + 8;
+        let end = (body + len).min(bytes.len());
+        if id == b"fmt " && len >= 16 {
+            // format tag, channels, sample rate
+            format = Some((u16_at(body), u16_at(body + 2), u32_at(body + 4)));
+        } else if id == b"data" {
+            data = Some(&bytes[body..end]);
+        }
+        // chunks are padded to an even length
+        offset = body + len + (len & 1);
+    }
+
+    let (tag, channels, sample_rate) = format?;
+    let decode = match tag {
+        FORMAT_MULAW => decode_mulaw,
+        FORMAT_ALAW => decode_alaw,
+        _ => return None,
+    };
+
+    let channels = (channels as usize).max(1);
+    let samples = data?
+        .iter()
+        .step_by(channels)
+        .map(|byte| decode(*byte) as f32)
+        .collect();
+
+    Some((samples, sample_rate as usize))
+}
+
+fn decode_mulaw(byte: u8) -> i16 {
+    let byte = !byte;
+    let magnitude = ((((byte & 0x0f) as i16) << 3) + 0x84) << ((byte & 0x70) >> 4);
+    match byte & 0x80 {
+        0 => magnitude - 0x84,
+        _ => 0x84 - magnitude,
+    }
+}
+
+fn decode_alaw(byte: u8) -> i16 {
+    let byte = byte ^ 0x55;
+    let mut magnitude = ((byte & 0x0f) as i16) << 4;
+    match (byte & 0x70) >> 4 {
+        0 => magnitude += 8,
+        segment => magnitude = (magnitude + 0x108) << (segment - 1),
+    }
+    match byte & 0x80 {
+        0 => -magnitude,
+        _ => magnitude,
+    }
+}
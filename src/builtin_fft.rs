@@ -0,0 +1,112 @@
+use crate::Complex;
+use crate::SampleReal;
+use crate::fft::{FftBackend, FftNormalization};
+
+use std::f64::consts::TAU;
+
+/// Self-contained radix-2 FFT backend, for builds without `realfft`
+/// (see the `builtin-fft` feature).
+///
+/// Real transforms are computed as complex ones of the same size,
+/// with an iterative Cooley-Tukey FFT: it's simple and has no
+/// dependencies, but it's several times slower than `realfft`,
+/// which also halves the transform size for real input; shifting
+/// with 1024 to 4096 samples frames takes about 1.5 times as long
+/// overall. Only powers of two are supported: frames of other
+/// sizes are zero-padded to the next one, as with
+/// [`PitchShifter::set_zero_padding`].
+///
+/// [`PitchShifter::set_zero_padding`]: crate::PitchShifter::set_zero_padding
+#[derive(Default)]
+pub struct BuiltinFftBackend {
+    fft_size: usize,
+    // `e^(-2iπk/N)` for `k` in `0..N/2`
+    twiddles: Vec<Complex<SampleReal>>,
+}
+
+impl BuiltinFftBackend {
+    /// Twiddle factors are computed on first use
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn plan(&mut self, fft_size: usize) {
+        assert!(fft_size.is_power_of_two(), "the builtin FFT only supports powers of two, got {}", fft_size);
+        if self.fft_size != fft_size {
+            self.fft_size = fft_size;
+            self.twiddles = (0..fft_size / 2).map(|k| {
+                let angle = -TAU * (k as f64) / (fft_size as f64);
+                Complex::new(angle.cos() as SampleReal, angle.sin() as SampleReal)
+            }).collect();
+        }
+    }
+
+    /// In-place forward transform of `data`, or inverse one (with
+    /// conjugated twiddles) if `inverse` is set
+    fn transform(&self, data: &mut [Complex<SampleReal>], inverse: bool) {
+        let len = data.len();
+        let bits = len.trailing_zeros();
+        if len > 1 {
+            for i in 0..len {
+                let j = i.reverse_bits() >> (usize::BITS - bits);
+                if i < j {
+                    data.swap(i, j);
+                }
+            }
+        }
+
+        let mut half = 1;
+        while half < len {
+            let stride = len / (half * 2);
+            for start in (0..len).step_by(half * 2) {
+                for k in 0..half {
+                    let twiddle = self.twiddles[k * stride];
+                    let twiddle = if inverse { twiddle.conj() } else { twiddle };
+                    let odd = data[start + k + half] * twiddle;
+                    data[start + k + half] = data[start + k] - odd;
+                    data[start + k] += odd;
+                }
+            }
+            half *= 2;
+        }
+    }
+}
+
+impl FftBackend for BuiltinFftBackend {
+    fn scratch_len(&mut self, fft_size: usize) -> usize {
+        self.plan(fft_size);
+        fft_size
+    }
+
+    fn supports_size(&self, fft_size: usize) -> bool {
+        fft_size.is_power_of_two()
+    }
+
+    fn forward(&mut self, input: &mut [SampleReal], output: &mut [Complex<SampleReal>], scratch: &mut [Complex<SampleReal>]) {
+        self.plan(input.len());
+        let data = &mut scratch[..input.len()];
+        data.iter_mut().zip(input.iter()).for_each(|(c, x)| *c = Complex::new(*x, 0.0));
+        self.transform(data, false);
+        output.copy_from_slice(&data[..output.len()]);
+    }
+
+    fn inverse(&mut self, input: &mut [Complex<SampleReal>], output: &mut [SampleReal], scratch: &mut [Complex<SampleReal>]) {
+        self.plan(output.len());
+        let len = output.len();
+        let data = &mut scratch[..len];
+
+        // the upper half mirrors the lower one; imaginary parts of
+        // DC & Nyquist only end up in the discarded imaginary output
+        data[..input.len()].copy_from_slice(input);
+        for k in input.len()..len {
+            data[k] = input[len - k].conj();
+        }
+
+        self.transform(data, true);
+        output.iter_mut().zip(data.iter()).for_each(|(x, c)| *x = c.re);
+    }
+
+    fn normalization(&self, _fft_size: usize) -> FftNormalization {
+        FftNormalization::UNNORMALIZED
+    }
+}
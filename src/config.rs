@@ -0,0 +1,92 @@
+use crate::SampleReal;
+use crate::WindowFunction;
+
+/// Configuration of a [`PitchShifter`], without any of its
+/// buffers or processing state.
+///
+/// It can be compared and hashed, e.g. to pool shifters by
+/// configuration. Build one with [`PitchShifterConfig::new`] and
+/// adjust its fields, or get one from [`PitchShifter::config`],
+/// then create shifters with [`PitchShifter::from_config`].
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::config`]: crate::PitchShifter::config
+/// [`PitchShifter::from_config`]: crate::PitchShifter::from_config
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PitchShifterConfig {
+    /// Length of the analysis windows, in samples; rounded up to
+    /// the next even number when building a shifter.
+    pub frame_size: usize,
+    /// Sample rate of the processed audio
+    pub sample_rate: usize,
+    /// See [`PitchShifter::set_analysis_window`](crate::PitchShifter::set_analysis_window)
+    pub analysis_window: WindowFunction,
+    /// See [`PitchShifter::set_synthesis_window`](crate::PitchShifter::set_synthesis_window)
+    pub synthesis_window: WindowFunction,
+    /// See [`PitchShifter::set_zero_padding`](crate::PitchShifter::set_zero_padding)
+    pub zero_padding: bool,
+    /// See [`PitchShifter::set_low_latency`](crate::PitchShifter::set_low_latency);
+    /// `frame_size` is the one before halving
+    pub low_latency: bool,
+    /// See [`PitchShifter::set_over_sampling`](crate::PitchShifter::set_over_sampling)
+    pub over_sampling: usize,
+}
+
+impl PitchShifterConfig {
+    /// Same parameters as [`PitchShifter::new`], with every other
+    /// setting at its default.
+    ///
+    /// [`PitchShifter::new`]: crate::PitchShifter::new
+    pub fn new(window_duration_ms: usize, sample_rate: usize) -> Self {
+        Self {
+            frame_size: sample_rate * window_duration_ms / 1000,
+            sample_rate,
+            analysis_window: WindowFunction::Hann,
+            synthesis_window: WindowFunction::Hann,
+            zero_padding: false,
+            low_latency: false,
+            over_sampling: 16,
+        }
+    }
+
+    /// Rounds [`PitchShifterConfig::frame_size`] to the nearest
+    /// power of two (the larger one on a tie), for which FFTs are
+    /// fastest.
+    ///
+    /// With the same duration, higher sample rates need larger
+    /// frames, and arbitrary sizes can be much slower to transform.
+    /// Quantizing keeps the cost predictable, while the duration
+    /// stays between 2/3 and 4/3 of the requested one:
+    /// a 50 ms window is 2048 samples (46.4 ms) at 44.1 kHz, and
+    /// 4096 samples (42.7 ms) at 96 kHz. See
+    /// [`PitchShifterConfig::window_duration_ms`] for the result.
+    ///
+    /// ```
+    /// use pitch_shift::PitchShifterConfig;
+    ///
+    /// let config = PitchShifterConfig::new(50, 96000).power_of_two_frame();
+    /// assert_eq!(config.frame_size, 4096);
+    /// assert!((config.window_duration_ms() - 42.67).abs() < 0.01);
+    /// ```
+    pub fn power_of_two_frame(mut self) -> Self {
+        let above = self.frame_size.next_power_of_two();
+        let below = above / 2;
+        self.frame_size = match self.frame_size - below < above - self.frame_size {
+            true => below,
+            false => above,
+        };
+        self
+    }
+
+    /// Actual duration of the windows, in milliseconds, after
+    /// rounding [`PitchShifterConfig::frame_size`] (and halving it
+    /// in low latency mode)
+    pub fn window_duration_ms(&self) -> SampleReal {
+        let mut frame_size = self.frame_size + self.frame_size % 2;
+        if self.low_latency {
+            frame_size /= 2;
+            frame_size += frame_size % 2;
+        }
+        1000.0 * (frame_size as SampleReal) / (self.sample_rate as SampleReal)
+    }
+}
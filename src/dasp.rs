@@ -0,0 +1,39 @@
+use crate::{PitchShifter, SampleReal};
+
+use dasp_frame::{Frame, N1};
+use dasp_sample::{FromSample, Sample, ToSample};
+use dasp_signal::Signal;
+
+/// Mono [`Signal`] of the output of a [`PitchShifter`] fed from
+/// another one, see [`PitchShifter::shift_signal`].
+///
+/// [`Signal`]: dasp_signal::Signal
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::shift_signal`]: crate::PitchShifter::shift_signal
+pub struct ShiftedSignal<'a, S> {
+    pub(crate) shifter: &'a mut PitchShifter,
+    pub(crate) signal: S,
+    pub(crate) over_sampling: usize,
+    // frequency ratio
+    pub(crate) shift: SampleReal,
+}
+
+impl<S> Signal for ShiftedSignal<'_, S>
+where
+    S: Signal,
+    S::Frame: Frame<NumChannels = N1>,
+    <S::Frame as Frame>::Sample: ToSample<SampleReal> + FromSample<SampleReal>,
+{
+    type Frame = S::Frame;
+
+    fn next(&mut self) -> S::Frame {
+        let frame = self.signal.next();
+        let sample = frame.channel(0).map_or(0.0, |sample| sample.to_sample());
+        let output = self.shifter.process_sample(self.over_sampling, self.shift, sample);
+        S::Frame::from_fn(|_| output.to_sample())
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.signal.is_exhausted()
+    }
+}
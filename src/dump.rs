@@ -0,0 +1,48 @@
+use crate::Complex;
+use crate::SampleReal;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// CSV writer behind [`PitchShifter::start_spectral_dump`]
+///
+/// Processing can't report I/O errors, so the first one is kept
+/// (further frames are then skipped) and returned when the dump
+/// is stopped.
+///
+/// [`PitchShifter::start_spectral_dump`]: crate::PitchShifter::start_spectral_dump
+pub(crate) struct SpectralDump {
+    writer: BufWriter<File>,
+    error: Option<io::Error>,
+}
+
+impl SpectralDump {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "frame,stage,bin,magnitude,phase")?;
+        Ok(Self { writer, error: None })
+    }
+
+    pub(crate) fn write(&mut self, frame: u64, stage: &str, spectrum: &[Complex<SampleReal>]) {
+        if self.error.is_some() {
+            return;
+        }
+
+        for (bin, value) in spectrum.iter().enumerate() {
+            let (magnitude, phase) = value.to_polar();
+            let line = writeln!(self.writer, "{},{},{},{},{}", frame, stage, bin, magnitude, phase);
+            if let Err(error) = line {
+                self.error = Some(error);
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        }
+    }
+}
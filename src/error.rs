@@ -0,0 +1,120 @@
+use std::fmt;
+
+/// Errors reported by the fallible methods of [`PitchShifter`],
+/// e.g. [`PitchShifter::try_new`].
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::try_new`]: crate::PitchShifter::try_new
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum PitchShiftError {
+    /// A sample rate of zero was given
+    ZeroSampleRate,
+    /// A window duration of zero milliseconds was given
+    ZeroWindowDuration,
+    /// The window would hold no samples at all, e.g. because its
+    /// duration is too short for the sample rate
+    EmptyWindow {
+        sample_rate: usize,
+    },
+    /// `over_sampling` must be in `1..=frame_size`; the message
+    /// lists the divisors of `frame_size`, which give equal hops
+    InvalidOverSampling {
+        over_sampling: usize,
+        frame_size: usize,
+    },
+    /// An overlap percentage outside of `0.0..100.0` was given
+    InvalidOverlap {
+        percent: f32,
+    },
+    /// No `over_sampling` dividing the frame size gives exactly the
+    /// requested overlap; the nearest one is suggested
+    InexactOverlap {
+        percent: f32,
+        nearest_over_sampling: usize,
+        nearest_percent: f32,
+    },
+    /// The input buffer is shorter than the output buffer
+    BufferTooShort {
+        input_len: usize,
+        output_len: usize,
+    },
+    /// Phase state slices must hold one value per bin
+    PhaseStateLength {
+        expected: usize,
+        last_phase_len: usize,
+        phase_sum_len: usize,
+    },
+    /// Windows given for a single call must hold one coefficient per
+    /// sample of the frame
+    WindowLength {
+        expected: usize,
+        analysis_len: usize,
+        synthesis_len: usize,
+    },
+    /// A [`WindowFunction::Custom`] window is set, which can't be
+    /// recomputed for another frame size
+    ///
+    /// [`WindowFunction::Custom`]: crate::WindowFunction::Custom
+    CustomWindowResize {
+        window_len: usize,
+        frame_size: usize,
+    },
+}
+
+impl fmt::Display for PitchShiftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ZeroSampleRate => write!(f, "sample_rate must be greater than zero"),
+            Self::ZeroWindowDuration => write!(f, "window_duration_ms must be greater than zero"),
+            Self::EmptyWindow { sample_rate } => write!(
+                f,
+                "window contains no samples (window duration too short for this sample rate?): sample_rate is {}",
+                sample_rate,
+            ),
+            Self::InvalidOverSampling { over_sampling, frame_size } => {
+                let divisors: Vec<String> = (1..=*frame_size)
+                    .filter(|divisor| frame_size.is_multiple_of(*divisor))
+                    .map(|divisor| divisor.to_string())
+                    .collect();
+                write!(
+                    f,
+                    "over_sampling must be between 1 and the frame size ({}), got {}; these divide it exactly: {}",
+                    frame_size, over_sampling, divisors.join(", "),
+                )
+            },
+            Self::InvalidOverlap { percent } => write!(
+                f,
+                "overlap must be at least 0% and less than 100%, got {}%",
+                percent,
+            ),
+            Self::InexactOverlap { percent, nearest_over_sampling, nearest_percent } => write!(
+                f,
+                "no over_sampling gives an overlap of {}% with this frame size; the nearest is {} ({}%)",
+                percent, nearest_over_sampling, nearest_percent,
+            ),
+            Self::BufferTooShort { input_len, output_len } => write!(
+                f,
+                "input buffer ({} samples) is shorter than output buffer ({} samples)",
+                input_len, output_len,
+            ),
+            Self::PhaseStateLength { expected, last_phase_len, phase_sum_len } => write!(
+                f,
+                "phase state must hold {} values per slice, got {} (last_phase) and {} (phase_sum)",
+                expected, last_phase_len, phase_sum_len,
+            ),
+            Self::WindowLength { expected, analysis_len, synthesis_len } => write!(
+                f,
+                "windows must hold {} coefficients (the frame size), got {} (analysis) and {} (synthesis)",
+                expected, analysis_len, synthesis_len,
+            ),
+            Self::CustomWindowResize { window_len, frame_size } => write!(
+                f,
+                "custom windows of {} coefficients can't be resized to a frame of {} samples; set other windows first",
+                window_len, frame_size,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PitchShiftError {}
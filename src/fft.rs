@@ -0,0 +1,134 @@
+use crate::Complex;
+use crate::SampleReal;
+
+#[cfg(feature = "realfft")]
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+
+#[cfg(feature = "realfft")]
+use std::sync::Arc;
+
+/// Scaling conventions of an FFT implementation, as factors
+/// relative to the unnormalized DFT, `X[k] = Σ x[n]·e^(-2iπkn/N)`,
+/// and to its unnormalized inverse, `x[n] = Σ X[k]·e^(2iπkn/N)`.
+///
+/// A forward/inverse round trip thus scales a signal by
+/// `forward * inverse * N`. The shifter compensates for both
+/// factors, so that every backend produces the same output level.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FftNormalization {
+    /// e.g. `1.0 / N` for a forward transform scaled by `1/N`
+    pub forward: SampleReal,
+    /// e.g. `1.0` for an unscaled inverse transform
+    pub inverse: SampleReal,
+}
+
+impl FftNormalization {
+    /// The convention of `realfft` & `rustfft`, and the baseline of
+    /// this crate: neither transform is scaled.
+    pub const UNNORMALIZED: Self = Self {
+        forward: 1.0,
+        inverse: 1.0,
+    };
+}
+
+/// Real FFT implementation used by a [`PitchShifter`], see
+/// [`PitchShifter::set_fft_backend`]
+///
+/// Backends must handle every even size the shifter asks for,
+/// planning (and caching) transforms as needed, unless they report
+/// otherwise with [`FftBackend::supports_size`]; the builtin one
+/// (see the `builtin-fft` feature) is limited to powers of two.
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::set_fft_backend`]: crate::PitchShifter::set_fft_backend
+pub trait FftBackend: Send {
+    /// Number of complex values of scratch space needed by
+    /// transforms of `fft_size` samples.
+    fn scratch_len(&mut self, fft_size: usize) -> usize;
+
+    /// Whether transforms of `fft_size` samples are supported;
+    /// frames of other sizes are zero-padded to the next power of
+    /// two (see [`PitchShifter::set_zero_padding`]), which every
+    /// backend must support. The default accepts every size.
+    ///
+    /// [`PitchShifter::set_zero_padding`]: crate::PitchShifter::set_zero_padding
+    fn supports_size(&self, _fft_size: usize) -> bool {
+        true
+    }
+
+    /// Real-to-complex transform of `input` into its
+    /// `input.len() / 2 + 1` first bins, in `output`.
+    ///
+    /// `input` may be overwritten.
+    fn forward(&mut self, input: &mut [SampleReal], output: &mut [Complex<SampleReal>], scratch: &mut [Complex<SampleReal>]);
+
+    /// Complex-to-real transform of the `output.len() / 2 + 1`
+    /// first bins in `input` into `output`.
+    ///
+    /// `input` may be overwritten.
+    fn inverse(&mut self, input: &mut [Complex<SampleReal>], output: &mut [SampleReal], scratch: &mut [Complex<SampleReal>]);
+
+    /// Scaling applied by transforms of `fft_size` samples
+    fn normalization(&self, fft_size: usize) -> FftNormalization;
+}
+
+/// The default backend, based on `realfft` (see the `realfft`
+/// feature, enabled by default)
+#[cfg(feature = "realfft")]
+#[derive(Default)]
+pub struct RealFftBackend {
+    planner: RealFftPlanner<SampleReal>,
+    forward: Option<Arc<dyn RealToComplex<SampleReal>>>,
+    inverse: Option<Arc<dyn ComplexToReal<SampleReal>>>,
+}
+
+#[cfg(feature = "realfft")]
+impl RealFftBackend {
+    /// Transforms are planned on first use
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn plan(&mut self, fft_size: usize) {
+        if self.forward.as_ref().map(|fft| fft.len()) != Some(fft_size) {
+            self.forward = Some(self.planner.plan_fft_forward(fft_size));
+            self.inverse = Some(self.planner.plan_fft_inverse(fft_size));
+        }
+    }
+}
+
+#[cfg(feature = "realfft")]
+impl FftBackend for RealFftBackend {
+    fn scratch_len(&mut self, fft_size: usize) -> usize {
+        self.plan(fft_size);
+        let forward = self.forward.as_ref().unwrap().get_scratch_len();
+        let inverse = self.inverse.as_ref().unwrap().get_scratch_len();
+        forward.max(inverse)
+    }
+
+    fn forward(&mut self, input: &mut [SampleReal], output: &mut [Complex<SampleReal>], scratch: &mut [Complex<SampleReal>]) {
+        self.plan(input.len());
+        let fft = self.forward.as_ref().unwrap();
+        let scratch_len = fft.get_scratch_len();
+        let _ = fft.process_with_scratch(input, output, &mut scratch[..scratch_len]);//.unwrap();
+    }
+
+    fn inverse(&mut self, input: &mut [Complex<SampleReal>], output: &mut [SampleReal], scratch: &mut [Complex<SampleReal>]) {
+        self.plan(output.len());
+        let fft = self.inverse.as_ref().unwrap();
+        let scratch_len = fft.get_scratch_len();
+        let _ = fft.process_with_scratch(input, output, &mut scratch[..scratch_len]);//.unwrap();
+    }
+
+    fn normalization(&self, _fft_size: usize) -> FftNormalization {
+        FftNormalization::UNNORMALIZED
+    }
+}
+
+/// Backend of new shifters
+#[cfg(feature = "realfft")]
+pub(crate) type DefaultFftBackend = RealFftBackend;
+
+/// Backend of new shifters
+#[cfg(all(feature = "builtin-fft", not(feature = "realfft")))]
+pub(crate) type DefaultFftBackend = crate::BuiltinFftBackend;
@@ -0,0 +1,51 @@
+use crate::{PitchShifter, SampleReal};
+
+/// Iterator over the output of a [`PitchShifter`] fed from another
+/// iterator, see [`PitchShifter::shift_iter`].
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::shift_iter`]: crate::PitchShifter::shift_iter
+pub struct ShiftedSamples<'a, I> {
+    pub(crate) shifter: &'a mut PitchShifter,
+    pub(crate) input: I,
+    pub(crate) over_sampling: usize,
+    // frequency ratio
+    pub(crate) shift: SampleReal,
+    // silent samples left to feed once `input` is exhausted
+    pub(crate) tail: Option<usize>,
+}
+
+impl<I: Iterator<Item = SampleReal>> Iterator for ShiftedSamples<'_, I> {
+    type Item = SampleReal;
+
+    fn next(&mut self) -> Option<SampleReal> {
+        let sample = match self.tail {
+            None => match self.input.next() {
+                Some(sample) => sample,
+                None => {
+                    let latency = self.shifter.latency_samples(self.over_sampling);
+                    self.tail = Some(latency);
+                    return self.next();
+                },
+            },
+            Some(0) => return None,
+            Some(ref mut left) => {
+                *left -= 1;
+                0.0
+            },
+        };
+
+        Some(self.shifter.process_sample(self.over_sampling, self.shift, sample))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.tail {
+            Some(left) => (left, Some(left)),
+            None => {
+                let latency = self.shifter.latency_samples(self.over_sampling);
+                let (low, high) = self.input.size_hint();
+                (low.saturating_add(latency), high.and_then(|high| high.checked_add(latency)))
+            },
+        }
+    }
+}
@@ -1,23 +1,405 @@
-use rustfft::FftPlanner;
-use realfft::RealToComplexEven;
-use realfft::ComplexToRealEven;
-use realfft::RealToComplex;
-use realfft::ComplexToReal;
-use realfft::num_complex::Complex;
+pub use num_complex::Complex;
 
+#[cfg(not(any(feature = "realfft", feature = "builtin-fft")))]
+compile_error!("an FFT backend is needed: enable the `realfft` or `builtin-fft` feature");
+
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::f32::consts::PI;
 use std::f32::consts::TAU; // = 2xPI
 
+mod window;
+mod rng;
+mod config;
+mod fft;
+#[cfg(feature = "builtin-fft")]
+mod builtin_fft;
+mod options;
+mod error;
+mod realtime;
+mod iter;
+mod streaming;
+mod planar;
+#[cfg(feature = "debug_dump")]
+mod dump;
+#[cfg(feature = "dasp")]
+mod dasp;
+#[cfg(feature = "wav")]
+mod wav;
+pub mod pitch;
+
+pub use window::WindowFunction;
+pub use window::WindowTables;
+pub use rng::Rng;
+pub use config::PitchShifterConfig;
+pub use fft::FftBackend;
+pub use fft::FftNormalization;
+#[cfg(feature = "realfft")]
+pub use fft::RealFftBackend;
+#[cfg(feature = "builtin-fft")]
+pub use builtin_fft::BuiltinFftBackend;
+pub use options::ProcessingOptions;
+pub use error::PitchShiftError;
+pub use realtime::RealtimeParameters;
+pub use iter::ShiftedSamples;
+pub use streaming::StreamingShifter;
+pub use planar::{complex_to_planar, planar_to_complex};
+#[cfg(feature = "dasp")]
+pub use dasp::ShiftedSignal;
+#[cfg(feature = "wav")]
+pub use wav::shift_wav_file;
+
 type SampleReal = f32;
 const COMPLEX_ZERO: Complex<SampleReal> = Complex::new(0.0, 0.0);
+const DEFAULT_MAGNITUDE_FLOOR: SampleReal = 1e-9;
+const COLA_TOLERANCE: SampleReal = 0.01;
+const DEESS_TRIGGER: SampleReal = 0.25;
+const TILT_PIVOT_HZ: SampleReal = 1000.0;
+// -40 dB below the loudest bin of the frame
+const PEAK_THRESHOLD: SampleReal = 0.01;
+const EXCITED_HARMONICS: usize = 4;
+const VOICED_FLATNESS: SampleReal = 0.3;
+// -20 dB below the loudest bin of the frame
+const FUNDAMENTAL_THRESHOLD: SampleReal = 0.1;
+const LOOP_FADE_SECONDS: SampleReal = 0.005;
+const AUTO_GAIN_SECONDS: SampleReal = 0.3;
+// +20 dB
+const AUTO_GAIN_MAX: SampleReal = 10.0;
+// -100 dB, in power
+const SILENCE_POWER: SampleReal = 1e-10;
+const ENVELOPE_SECONDS: SampleReal = 0.02;
+// width of the crossfade past each edge of a shifted band
+const BAND_EDGE_BINS: SampleReal = 2.0;
+const EXPANDER_ATTACK_SECONDS: SampleReal = 0.005;
+const EXPANDER_RELEASE_SECONDS: SampleReal = 0.1;
+const FRAME_GAIN_SECONDS: SampleReal = 0.02;
+// +12 dB
+const FRAME_GAIN_MAX: SampleReal = 4.0;
+// measures refining the fine tune correction
+const FINE_TUNE_ITERATIONS: usize = 3;
+
+/// How analysis bins are mapped to synthesis bins when shifting,
+/// see [`PitchShifter::set_bin_mapping`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum BinMapping {
+    /// Bin `k` goes to the nearest bin of `k * ratio`; the default.
+    ///
+    /// The realized pitch of each partial is off by at most half a
+    /// bin, and neighbouring bins collide (downwards) or leave
+    /// gaps (upwards) where the rounding lands them together.
+    #[default]
+    Round,
+    /// Bin `k` goes to the bin below `k * ratio`.
+    ///
+    /// Truncation biases partials downward by up to one bin,
+    /// which is audible on small frames, but collisions happen
+    /// on different bins than with rounding, changing the
+    /// character of the artifacts.
+    Floor,
+    /// The magnitude of bin `k` is split between the two bins
+    /// surrounding `k * ratio`, proportionally to proximity.
+    ///
+    /// This avoids gaps and holds the energy of each partial
+    /// at its fractional position, at the cost of smearing each
+    /// partial over two bins, which slightly widens peaks.
+    Interpolate,
+}
+
+impl BinMapping {
+    /// Synthesis bin receiving (most of) a bin moved to `target`
+    fn index(self, target: SampleReal) -> usize {
+        match self {
+            Self::Round => target.round() as usize,
+            Self::Floor | Self::Interpolate => target.floor() as usize,
+        }
+    }
+}
+
+/// Presets trading processing speed for quality,
+/// see [`PitchShifter::set_quality`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Quality {
+    /// `over_sampling` of 4, [`BinMapping::Round`]
+    Draft,
+    /// `over_sampling` of 8, [`BinMapping::Round`]
+    Normal,
+    /// `over_sampling` of 16, [`BinMapping::Interpolate`]
+    High,
+    /// `over_sampling` of 32, [`BinMapping::Interpolate`]
+    Ultra,
+}
+
+/// How the requested shift is snapped before being applied, see
+/// [`PitchShifter::set_shift_quantization`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum QuantMode {
+    /// The shift is applied as is; the default.
+    #[default]
+    Off,
+    /// The shift is rounded to the nearest semitone.
+    Semitone,
+    /// The shift is rounded to the nearest semitone belonging to
+    /// this scale, given as degrees in `0..12` semitones above the
+    /// unshifted pitch; e.g. `vec![0, 2, 4, 5, 7, 9, 11]` for a
+    /// major scale. Degrees are taken modulo 12, and an empty scale
+    /// behaves like `Off`.
+    Scale(Vec<u8>),
+}
+
+impl QuantMode {
+    fn apply(&self, shift: SampleReal) -> SampleReal {
+        match self {
+            Self::Off => shift,
+            Self::Semitone => shift.round(),
+            Self::Scale(degrees) if degrees.is_empty() => shift,
+            Self::Scale(degrees) => {
+                let in_scale = |semitone: i32| degrees.iter().any(|d| (*d as i32) % 12 == semitone.rem_euclid(12));
+                let nearest = shift.round() as i32;
+                // any scale has a degree within 6 semitones
+                (0..=6)
+                    .flat_map(|distance| [nearest - distance, nearest + distance])
+                    .filter(|semitone| in_scale(*semitone))
+                    .min_by(|a, b| {
+                        let distance = |semitone: &i32| (*semitone as SampleReal - shift).abs();
+                        distance(a).total_cmp(&distance(b))
+                    })
+                    .unwrap_or(nearest) as SampleReal
+            },
+        }
+    }
+}
+
+/// How the phases of synthesized bins advance from frame to
+/// frame, see [`PitchShifter::set_phase_mode`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PhaseMode {
+    /// Each bin advances by its own measured frequency; the
+    /// default.
+    #[default]
+    Standard,
+    /// In voiced frames, bins around each harmonic of the detected
+    /// fundamental advance by exactly that harmonic's frequency,
+    /// so that harmonics keep the same phase relationship to the
+    /// fundamental from frame to frame.
+    ///
+    /// This removes much of the "underwater" smearing of sustained
+    /// monophonic notes, but it assumes a single harmonic source:
+    /// chords, inharmonic or noisy content get pulled onto the
+    /// harmonics of whatever fundamental is found, which sounds
+    /// worse than [`PhaseMode::Standard`].
+    HarmonicLocked,
+}
+
+/// A local maximum of the shifted spectrum, see
+/// [`PitchShifter::last_peaks`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Peak {
+    /// Index of the bin holding the peak
+    pub bin: usize,
+    /// Magnitude of that bin
+    pub magnitude: SampleReal,
+    /// Estimated true frequency of the partial, in Hz; this is
+    /// more precise than the center frequency of the bin.
+    pub frequency: SampleReal,
+}
+
+/// Factors scaling each resynthesized frame before overlap-add,
+/// see [`PitchShifter::synthesis_scaling`]; their product is applied
+/// to every sample of the frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SynthesisScaling {
+    /// Undoes the gain of the FFT round trip: `1 / ((fft_size / 2 + 1)
+    /// * forward * inverse)`, with the normalization factors of the
+    /// [`FftBackend`]. An unnormalized round trip scales by `fft_size`,
+    /// so this leaves a gain of about `2`.
+    pub transform: SampleReal,
+    /// Undoes the overlap of frames: `1 / over_sampling`
+    pub overlap: SampleReal,
+    /// Brings the overlap sum of the analysis and synthesis windows
+    /// to the level of the default Hann/Hann pair, see
+    /// [`PitchShifter::set_analysis_window`]. It's `1.0` for Hann/Hann,
+    /// whose overlap sum, with the other factors, makes unshifted
+    /// audio come out at about `0.75` times its input level.
+    pub window: SampleReal,
+    /// Extra output gain, `1.0` unless overridden
+    pub gain: SampleReal,
+}
+
+impl SynthesisScaling {
+    /// Product of all factors
+    pub fn total(&self) -> SampleReal {
+        self.transform * self.overlap * self.window * self.gain
+    }
+}
+
+/// See [`PitchShifter::set_auto_gain_match`]
+#[derive(Clone, Debug)]
+struct AutoGain {
+    // input samples, delayed to line up with the output
+    delay: VecDeque<SampleReal>,
+    coeff: SampleReal,
+    input_power: SampleReal,
+    output_power: SampleReal,
+    gain: SampleReal,
+}
+
+impl AutoGain {
+    fn new(sample_rate: usize, latency: usize) -> Self {
+        let time_constant = AUTO_GAIN_SECONDS * sample_rate as SampleReal;
+        Self {
+            delay: VecDeque::with_capacity(latency + 1),
+            coeff: (-1.0 / time_constant).exp(),
+            input_power: 0.0,
+            output_power: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    /// Back to the initial state, for a `latency` which may have
+    /// changed; this only allocates if it grew
+    fn reset(&mut self, latency: usize) {
+        self.delay.clear();
+        self.delay.reserve(latency + 1);
+        self.input_power = 0.0;
+        self.output_power = 0.0;
+        self.gain = 1.0;
+    }
+
+    fn process(&mut self, latency: usize, input: SampleReal, output: SampleReal) -> SampleReal {
+        let coeff = self.coeff;
+        let follow = |power: &mut SampleReal, sample: SampleReal| {
+            *power = coeff * *power + (1.0 - coeff) * sample * sample;
+        };
+
+        self.delay.push_back(input);
+        if self.delay.len() > latency {
+            let delayed = self.delay.pop_front().unwrap();
+            follow(&mut self.input_power, delayed);
+        }
+        // in case the latency dropped without a reset
+        let excess = self.delay.len().saturating_sub(latency);
+        self.delay.drain(..excess);
+
+        follow(&mut self.output_power, output);
+
+        // hold the gain through silence, rather than chasing noise
+        if self.output_power > SILENCE_POWER && self.input_power > SILENCE_POWER {
+            let target = (self.input_power / self.output_power).sqrt().min(AUTO_GAIN_MAX);
+            self.gain = coeff * self.gain + (1.0 - coeff) * target;
+        }
+
+        output * self.gain
+    }
+}
+
+/// See [`PitchShifter::set_highpass_hz`]
+#[derive(Copy, Clone, Debug)]
+struct HighPass {
+    cutoff_hz: SampleReal,
+    coeff: SampleReal,
+    previous_input: SampleReal,
+    previous_output: SampleReal,
+}
+
+impl HighPass {
+    fn new(cutoff_hz: SampleReal, sample_rate: usize) -> Self {
+        Self {
+            cutoff_hz,
+            coeff: (-TAU * cutoff_hz / sample_rate as SampleReal).exp(),
+            previous_input: 0.0,
+            previous_output: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: SampleReal) -> SampleReal {
+        let output = self.coeff * (self.previous_output + sample - self.previous_input);
+        self.previous_input = sample;
+        self.previous_output = output;
+        output
+    }
+}
+
+/// See [`PitchShifter::set_emphasis`]
+#[derive(Clone, Debug)]
+struct Emphasis {
+    coeff: SampleReal,
+    previous_input: SampleReal,
+    // de-emphasis gain of each bin
+    gains: Vec<SampleReal>,
+}
+
+impl Emphasis {
+    fn new(coeff: SampleReal, fft_size: usize) -> Self {
+        let gains = (0..(fft_size / 2) + 1).map(|k| {
+            let omega = TAU * k as SampleReal / fft_size as SampleReal;
+            1.0 / (1.0 - 2.0 * coeff * omega.cos() + coeff * coeff).sqrt()
+        }).collect();
+
+        Self {
+            coeff,
+            previous_input: 0.0,
+            gains,
+        }
+    }
+
+    fn process(&mut self, sample: SampleReal) -> SampleReal {
+        let output = sample - self.coeff * self.previous_input;
+        self.previous_input = sample;
+        output
+    }
+}
+
+/// See [`PitchShifter::set_deess`]
+#[derive(Copy, Clone, Debug)]
+struct DeEsser {
+    low_hz: SampleReal,
+    high_hz: SampleReal,
+    reduction_db: SampleReal,
+    gain: SampleReal,
+}
+
+/// See [`PitchShifter::set_frame_gain_normalization`]
+#[derive(Copy, Clone, Debug)]
+struct FrameGain {
+    // energy of the frame in progress, had it been left unchanged
+    reference: SampleReal,
+    // smoothed gain of the last frame
+    gain: SampleReal,
+}
+
+/// See [`PitchShifter::set_expander`]
+#[derive(Copy, Clone, Debug)]
+struct Expander {
+    threshold_db: SampleReal,
+    ratio: SampleReal,
+    knee_db: SampleReal,
+    // smoothed gain of the last frame
+    gain_db: SampleReal,
+}
+
+impl Expander {
+    /// Static curve: gain for a level of `level_db`
+    fn target_db(&self, level_db: SampleReal) -> SampleReal {
+        let below = level_db - self.threshold_db;
+        let half_knee = self.knee_db * 0.5;
+        if below >= half_knee {
+            0.0
+        } else if below <= -half_knee {
+            (self.ratio - 1.0) * below
+        } else {
+            let into_knee = below - half_knee;
+            -(self.ratio - 1.0) * into_knee * into_knee / (2.0 * self.knee_db)
+        }
+    }
+}
 
 /// See [`PitchShifter::new`] & [`PitchShifter::shift_pitch`]
 pub struct PitchShifter {
-    forward_fft: RealToComplexEven<SampleReal>,
-    inverse_fft: ComplexToRealEven<SampleReal>,
-    ffft_scratch_len: usize,
-    ifft_scratch_len: usize,
+    fft: Box<dyn FftBackend>,
     fft_scratch: Vec<Complex<SampleReal>>,
+    // needed by `fft`, `fft_scratch` being empty after
+    // `process_with_scratch`
+    scratch_len: usize,
     fft_real: Vec<SampleReal>,
     fft_cplx: Vec<Complex<SampleReal>>,
 
@@ -26,14 +408,104 @@ pub struct PitchShifter {
 
     last_phase: Vec<SampleReal>,
     phase_sum: Vec<SampleReal>,
-    windowing: Vec<SampleReal>,
+    analysis_function: WindowFunction,
+    synthesis_function: WindowFunction,
+    analysis_window: Arc<[SampleReal]>,
+    synthesis_window: Arc<[SampleReal]>,
+    window_compensation: SampleReal,
     output_accumulator: Vec<SampleReal>,
+    // replaces output_accumulator when not empty
+    wide_accumulator: Vec<f64>,
     synthesized_frequency: Vec<SampleReal>,
     synthesized_magnitude: Vec<SampleReal>,
 
     frame_size: usize,
+    // `frame_size` outside of low latency mode
+    full_frame_size: usize,
+    low_latency: bool,
+    fft_size: usize,
+    zero_padding: bool,
     overlap: usize,
+    started: bool,
+    start_offset: usize,
     sample_rate: usize,
+
+    over_sampling: usize,
+    shift: SampleReal,
+    pending: VecDeque<SampleReal>,
+    bypass_on_unity: bool,
+    magnitude_floor: SampleReal,
+    bin_mapping: BinMapping,
+    frames: u64,
+    rng: Rng,
+    deess: Option<DeEsser>,
+    expander: Option<Expander>,
+    frame_gain: Option<FrameGain>,
+    spectral_tilt: SampleReal,
+    tilt_gains: Vec<SampleReal>,
+    harmonic_excite: SampleReal,
+    peaks: Vec<Peak>,
+    voiced: bool,
+    flatness: SampleReal,
+    phase_coherence: SampleReal,
+    centroid: SampleReal,
+    peak_bin: usize,
+    energy_preservation: bool,
+    magnitude_smoothing: SampleReal,
+    smoothed_magnitude: Vec<SampleReal>,
+    // whether `smoothed_magnitude` holds a previous frame
+    smoothing_primed: bool,
+    last_over_sampling: usize,
+    // `over_sampling` of the hop in progress
+    hop_over_sampling: usize,
+    last_shift: SampleReal,
+    carry: VecDeque<SampleReal>,
+    // input delayed by `frame_size`, see `shift_pitch_split`
+    dry_delay: VecDeque<SampleReal>,
+    limiter_ceiling: Option<SampleReal>,
+    clip_ceiling: SampleReal,
+    // output samples above `clip_ceiling` in the last call
+    clip_count: usize,
+    auto_gain: Option<AutoGain>,
+    retune_hz: Option<SampleReal>,
+    band_hz: Option<(SampleReal, SampleReal)>,
+    // analysis magnitudes of the last frame, while recording them
+    spectrogram: Option<Vec<SampleReal>>,
+    // `spectrogram`'s allocation, kept between recordings
+    spectrogram_buffer: Vec<SampleReal>,
+    // added to the position of every bin, in bins
+    bin_offset: Option<SampleReal>,
+    // base shift & depth, in semitones
+    envelope_mod: Option<(SampleReal, SampleReal)>,
+    envelope: SampleReal,
+    first_frame_phase_reset: bool,
+    // whether `last_phase` holds the phases of a previous frame
+    phases_primed: bool,
+    // whether the previous frame was bypassed, see `bypass_frame`
+    bypassed: bool,
+    // with the last generation applied
+    realtime: Option<(RealtimeParameters, u64)>,
+    #[cfg(feature = "debug_dump")]
+    spectral_dump: Option<dump::SpectralDump>,
+    // input samples waiting to be resampled by `time_stretch`
+    stretch_input: VecDeque<SampleReal>,
+    // output samples produced & input samples dropped since the
+    // beginning of the stream
+    stretch_outputs: u64,
+    stretch_consumed: u64,
+    highpass: Option<HighPass>,
+    emphasis: Option<Emphasis>,
+    // samples done & total length of the fade-in after a loop point
+    loop_fade: (usize, usize),
+    loop_phase_reset: bool,
+    scaling_override: Option<SynthesisScaling>,
+    quantization: QuantMode,
+    phase_mode: PhaseMode,
+    phase_dispersion: SampleReal,
+    reverse_processing: bool,
+    fine_tune: bool,
+    // `over_sampling` & ratio measured, and the correction
+    fine_tune_correction: Option<(usize, SampleReal, SampleReal)>,
 }
 
 impl PitchShifter {
@@ -48,32 +520,123 @@ impl PitchShifter {
     /// rate of the buffer(s) you will provide to
     /// [`PitchShifter::shift_pitch`], which is how many values
     /// correspond to one second of audio in the buffer.
+    ///
+    /// The duration is converted to a number of samples (rounded
+    /// down), which is then rounded up to the next even number,
+    /// as in [`PitchShifter::with_window_samples`].
+    ///
+    /// Panics if either argument is zero, or if the window is
+    /// too short to contain a single sample; see
+    /// [`PitchShifter::try_new`] for a non-panicking version.
     pub fn new(window_duration_ms: usize, sample_rate: usize) -> Self {
-        let mut frame_size = sample_rate * window_duration_ms / 1000;
-        frame_size += frame_size % 2;
-        let fs_real = frame_size as SampleReal;
+        Self::try_new(window_duration_ms, sample_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let double_frame_size = frame_size * 2;
-        let half_frame_size = (frame_size / 2) + 1;
+    /// Same as [`PitchShifter::new`], returning an error instead
+    /// of panicking on invalid arguments.
+    pub fn try_new(window_duration_ms: usize, sample_rate: usize) -> Result<Self, PitchShiftError> {
+        if sample_rate == 0 {
+            return Err(PitchShiftError::ZeroSampleRate);
+        }
+
+        if window_duration_ms == 0 {
+            return Err(PitchShiftError::ZeroWindowDuration);
+        }
+
+        Self::try_with_window_samples(sample_rate * window_duration_ms / 1000, sample_rate)
+    }
+
+    /// Same as [`PitchShifter::new`], but with the window duration
+    /// given as a number of samples.
+    ///
+    /// Windows must have an even length: an odd `samples` is
+    /// rounded up to the next even number, so the resulting
+    /// [`PitchShifter::frame_size`] may be one more than requested.
+    ///
+    /// Panics if either argument is zero; see
+    /// [`PitchShifter::try_with_window_samples`] for a non-panicking
+    /// version.
+    pub fn with_window_samples(samples: usize, sample_rate: usize) -> Self {
+        Self::try_with_window_samples(samples, sample_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let mut planner = FftPlanner::new();
-        let forward_fft = RealToComplexEven::new(frame_size, &mut planner);
-        let inverse_fft = ComplexToRealEven::new(frame_size, &mut planner);
-        let ffft_scratch_len = forward_fft.get_scratch_len();
-        let ifft_scratch_len = inverse_fft.get_scratch_len();
-        let scratch_len = ffft_scratch_len.max(ifft_scratch_len);
+    /// Same as [`PitchShifter::with_window_samples`], returning an
+    /// error instead of panicking on invalid arguments.
+    pub fn try_with_window_samples(samples: usize, sample_rate: usize) -> Result<Self, PitchShiftError> {
+        if sample_rate == 0 {
+            return Err(PitchShiftError::ZeroSampleRate);
+        }
 
-        let mut windowing = vec![0.0; frame_size];
-        for k in 0..frame_size {
-            windowing[k] = -0.5 * (TAU * (k as SampleReal) / fs_real).cos() + 0.5;
+        if samples == 0 {
+            return Err(PitchShiftError::EmptyWindow { sample_rate });
         }
 
-        Self {
-            forward_fft,
-            inverse_fft,
-            ffft_scratch_len,
-            ifft_scratch_len,
+        let frame_size = samples + samples % 2;
+        let windowing: Arc<[SampleReal]> = WindowFunction::Hann.coefficients(frame_size).into();
+        let tables = WindowTables {
+            frame_size,
+            analysis_function: WindowFunction::Hann,
+            synthesis_function: WindowFunction::Hann,
+            analysis: windowing.clone(),
+            synthesis: windowing,
+            compensation: 1.0,
+        };
+
+        Ok(Self::from_tables(&tables, sample_rate))
+    }
+
+    /// Same as [`PitchShifter::with_window_samples`], with windows
+    /// computed beforehand, see [`WindowTables`]. The shifter is
+    /// otherwise identical to one built for the same frame size,
+    /// then given the same windows.
+    ///
+    /// Panics if `sample_rate` is zero; see
+    /// [`PitchShifter::try_with_window_tables`] for a non-panicking
+    /// version.
+    pub fn with_window_tables(tables: &WindowTables, sample_rate: usize) -> Self {
+        Self::try_with_window_tables(tables, sample_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`PitchShifter::with_window_tables`], returning an
+    /// error instead of panicking on invalid arguments.
+    pub fn try_with_window_tables(tables: &WindowTables, sample_rate: usize) -> Result<Self, PitchShiftError> {
+        if sample_rate == 0 {
+            return Err(PitchShiftError::ZeroSampleRate);
+        }
+
+        Ok(Self::from_tables(tables, sample_rate))
+    }
+
+    /// The windows of this shifter, to build others with
+    /// [`PitchShifter::with_window_tables`]
+    pub fn window_tables(&self) -> WindowTables {
+        WindowTables {
+            frame_size: self.frame_size,
+            analysis_function: self.analysis_function.clone(),
+            synthesis_function: self.synthesis_function.clone(),
+            analysis: self.analysis_window.clone(),
+            synthesis: self.synthesis_window.clone(),
+            compensation: self.window_compensation,
+        }
+    }
+
+    fn from_tables(tables: &WindowTables, sample_rate: usize) -> Self {
+        let frame_size = tables.frame_size;
+        let double_frame_size = frame_size * 2;
+        let half_frame_size = (frame_size / 2) + 1;
+
+        let mut fft = fft::DefaultFftBackend::new();
+        // other sizes are padded by `set_zero_padding` below
+        let supported = fft.supports_size(frame_size);
+        let scratch_len = match supported {
+            true => fft.scratch_len(frame_size),
+            false => 0,
+        };
+
+        let mut shifter = Self {
+            fft: Box::new(fft),
             fft_scratch: vec![COMPLEX_ZERO; scratch_len],
+            scratch_len,
             fft_real: vec![0.0; frame_size],
             fft_cplx: vec![COMPLEX_ZERO; half_frame_size],
 
@@ -82,15 +645,379 @@ impl PitchShifter {
 
             last_phase: vec![0.0; half_frame_size],
             phase_sum: vec![0.0; half_frame_size],
-            windowing,
+            analysis_function: tables.analysis_function.clone(),
+            synthesis_function: tables.synthesis_function.clone(),
+            analysis_window: tables.analysis.clone(),
+            synthesis_window: tables.synthesis.clone(),
+            window_compensation: tables.compensation,
             output_accumulator: vec![0.0; double_frame_size],
-            synthesized_frequency: vec![0.0; frame_size],
-            synthesized_magnitude: vec![0.0; frame_size],
+            wide_accumulator: Vec::new(),
+            synthesized_frequency: vec![0.0; half_frame_size],
+            synthesized_magnitude: vec![0.0; half_frame_size],
 
             frame_size,
+            full_frame_size: frame_size,
+            low_latency: false,
+            fft_size: frame_size,
+            zero_padding: false,
             overlap: 0,
+            started: false,
+            start_offset: 0,
             sample_rate,
+
+            over_sampling: 16,
+            shift: 0.0,
+            pending: VecDeque::new(),
+            bypass_on_unity: false,
+            magnitude_floor: DEFAULT_MAGNITUDE_FLOOR,
+            bin_mapping: BinMapping::Round,
+            frames: 0,
+            rng: Rng::default(),
+            deess: None,
+            expander: None,
+            frame_gain: None,
+            spectral_tilt: 0.0,
+            tilt_gains: Vec::new(),
+            harmonic_excite: 0.0,
+            peaks: Vec::new(),
+            voiced: false,
+            flatness: 1.0,
+            phase_coherence: 1.0,
+            centroid: 0.0,
+            peak_bin: 0,
+            energy_preservation: false,
+            magnitude_smoothing: 0.0,
+            smoothed_magnitude: vec![0.0; half_frame_size],
+            smoothing_primed: false,
+            last_over_sampling: 16,
+            hop_over_sampling: 16,
+            last_shift: 1.0,
+            carry: VecDeque::new(),
+            dry_delay: VecDeque::new(),
+            limiter_ceiling: None,
+            clip_ceiling: 1.0,
+            clip_count: 0,
+            auto_gain: None,
+            retune_hz: None,
+            band_hz: None,
+            spectrogram: None,
+            spectrogram_buffer: Vec::new(),
+            bin_offset: None,
+            envelope_mod: None,
+            envelope: 0.0,
+            first_frame_phase_reset: false,
+            phases_primed: false,
+            bypassed: false,
+            realtime: None,
+            #[cfg(feature = "debug_dump")]
+            spectral_dump: None,
+            stretch_input: VecDeque::new(),
+            stretch_outputs: 0,
+            stretch_consumed: 0,
+            highpass: None,
+            emphasis: None,
+            loop_fade: (0, 0),
+            loop_phase_reset: false,
+            scaling_override: None,
+            quantization: QuantMode::Off,
+            phase_mode: PhaseMode::Standard,
+            phase_dispersion: 0.0,
+            reverse_processing: false,
+            fine_tune: false,
+            fine_tune_correction: None,
+        };
+
+        if !supported {
+            shifter.set_zero_padding(false);
+        }
+        shifter
+    }
+
+    /// Builds a shifter from a [`PitchShifterConfig`]
+    pub fn from_config(config: &PitchShifterConfig) -> Self {
+        let mut shifter = Self::with_window_samples(config.frame_size, config.sample_rate);
+        shifter.set_zero_padding(config.zero_padding);
+        // before the windows, which span the (halved) frame
+        shifter.set_low_latency(config.low_latency);
+        shifter.set_analysis_window(config.analysis_window.clone());
+        shifter.set_synthesis_window(config.synthesis_window.clone());
+        shifter.set_over_sampling(config.over_sampling);
+        shifter
+    }
+
+    /// The configuration of this shifter, which can rebuild an
+    /// identical one (without its processing state) with
+    /// [`PitchShifter::from_config`].
+    pub fn config(&self) -> PitchShifterConfig {
+        PitchShifterConfig {
+            frame_size: self.full_frame_size,
+            sample_rate: self.sample_rate,
+            analysis_window: self.analysis_function.clone(),
+            synthesis_window: self.synthesis_function.clone(),
+            zero_padding: self.zero_padding,
+            low_latency: self.low_latency,
+            over_sampling: self.over_sampling,
+        }
+    }
+
+    /// Every processing option currently set, for logging and
+    /// reproducibility; see [`ProcessingOptions`].
+    pub fn options(&self) -> ProcessingOptions {
+        ProcessingOptions {
+            config: self.config(),
+            fft_size: self.fft_size,
+            shift: self.shift,
+            bin_mapping: self.bin_mapping,
+            bypass_on_unity: self.bypass_on_unity,
+            magnitude_floor: self.magnitude_floor,
+            energy_preservation: self.energy_preservation,
+            magnitude_smoothing: self.magnitude_smoothing,
+            deess: self.deess.map(|d| ((d.low_hz, d.high_hz), d.reduction_db)),
+            expander: self.expander.map(|e| (e.threshold_db, e.ratio, e.knee_db)),
+            spectral_tilt: self.spectral_tilt,
+            harmonic_excite: self.harmonic_excite,
+            limiter_ceiling: self.limiter_ceiling,
+            clip_ceiling: self.clip_ceiling,
+            start_offset: self.start_offset,
+            auto_gain_match: self.auto_gain.is_some(),
+            frame_gain_normalization: self.frame_gain.is_some(),
+            first_frame_phase_reset: self.first_frame_phase_reset,
+            highpass_hz: self.highpass.map(|h| h.cutoff_hz),
+            emphasis: self.emphasis.as_ref().map(|e| e.coeff),
+            quantization: self.quantization.clone(),
+            phase_mode: self.phase_mode,
+            phase_dispersion: self.phase_dispersion,
+            reverse_processing: self.reverse_processing,
+            wide_accumulator: !self.wide_accumulator.is_empty(),
+            fine_tune: self.fine_tune,
+            synthesis_scaling: self.scaling_override,
+        }
+    }
+
+    /// Replaces the FFT implementation, which is `RealFftBackend`
+    /// by default (`BuiltinFftBackend` without the `realfft`
+    /// feature).
+    ///
+    /// The output level is kept the same whatever the backend's
+    /// normalization conventions, as long as it reports them
+    /// correctly (see [`FftNormalization`]). Absolute magnitude
+    /// settings, like [`PitchShifter::set_magnitude_floor`], are
+    /// however relative to the backend's forward scaling.
+    ///
+    /// Frames are zero-padded if `backend` can't transform their
+    /// size, see [`FftBackend::supports_size`]. This calls
+    /// [`PitchShifter::reset`].
+    pub fn set_fft_backend(&mut self, backend: impl FftBackend + 'static) {
+        self.fft = Box::new(backend);
+        // pads (or unpads) frames for the new backend
+        self.set_zero_padding(self.zero_padding);
+        self.scratch_len = self.fft.scratch_len(self.fft_size);
+        self.fft_scratch = vec![COMPLEX_ZERO; self.scratch_len];
+        self.reset();
+    }
+
+    /// Length of the analysis windows, in samples
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Length of the FFTs, in samples; see
+    /// [`PitchShifter::set_zero_padding`] and
+    /// [`PitchShifter::set_low_latency`]
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Whether [`PitchShifter::fft_size`] is one of the sizes FFTs
+    /// are fastest for: an even number with no prime factors other
+    /// than 2, 3 and 5, e.g. 2048, 2000 or 2160.
+    ///
+    /// The `realfft` backend transforms these about as fast as
+    /// powers of two; sizes with large prime factors need slower
+    /// algorithms, and can make processing take twice as long
+    /// overall. If a duration passed to [`PitchShifter::new`] gives
+    /// such a size, pick one with [`PitchShifter::with_window_samples`]
+    /// (see [`PitchShifterConfig::power_of_two_frame`]) or enable
+    /// [`PitchShifter::set_zero_padding`]. The builtin backend pads
+    /// every other size to a power of two anyway.
+    pub fn is_fft_fast(&self) -> bool {
+        let mut size = self.fft_size;
+        for factor in [2, 3, 5] {
+            while size.is_multiple_of(factor) {
+                size /= factor;
+            }
+        }
+        size == 1 && self.fft_size.is_multiple_of(2)
+    }
+
+    /// When enabled, frames are zero-padded up to the next power
+    /// of two before the forward FFT, which is much faster than an
+    /// awkward size (e.g. one with large prime factors) as chosen
+    /// by [`PitchShifter::new`] for arbitrary durations.
+    ///
+    /// The window still covers the frame only, which is centered
+    /// in the FFT input, with zeros on both sides.
+    /// The hop, latency and time resolution are those of the
+    /// frame, but bins get narrower (`sample_rate / fft_size` Hz
+    /// instead of `sample_rate / frame_size`): padding interpolates
+    /// the spectrum, it doesn't add actual frequency resolution.
+    /// The cost is a bit more memory for the spectral buffers.
+    ///
+    /// Frames are padded this way whatever `zero_padding` if the
+    /// FFT backend can't transform their size (see
+    /// [`FftBackend::supports_size`]), as with the builtin one for
+    /// sizes other than powers of two. This calls
+    /// [`PitchShifter::reset`].
+    pub fn set_zero_padding(&mut self, zero_padding: bool) {
+        self.zero_padding = zero_padding;
+        let padded = match self.low_latency {
+            true => self.frame_size * 2,
+            false => self.frame_size,
+        };
+        let fft_size = match zero_padding || !self.fft.supports_size(padded) {
+            true => padded.next_power_of_two(),
+            false => padded,
+        };
+
+        if fft_size != self.fft_size {
+            let half_fft_size = (fft_size / 2) + 1;
+            self.scratch_len = self.fft.scratch_len(fft_size);
+
+            self.fft_scratch = vec![COMPLEX_ZERO; self.scratch_len];
+            self.fft_real = vec![0.0; fft_size];
+            self.fft_cplx = vec![COMPLEX_ZERO; half_fft_size];
+            self.last_phase = vec![0.0; half_fft_size];
+            self.phase_sum = vec![0.0; half_fft_size];
+            self.synthesized_frequency = vec![0.0; half_fft_size];
+            self.synthesized_magnitude = vec![0.0; half_fft_size];
+            self.smoothed_magnitude = vec![0.0; half_fft_size];
+            self.fft_size = fft_size;
+            self.set_spectral_tilt(self.spectral_tilt);
+            if let Some(emphasis) = &self.emphasis {
+                self.emphasis = Some(Emphasis::new(emphasis.coeff, fft_size));
+            }
+        }
+
+        self.reset();
+        self.update_fine_tune();
+    }
+
+    /// Changes the window duration, as passed to
+    /// [`PitchShifter::new`], keeping the other settings.
+    ///
+    /// This is cheaper than building a new shifter: the FFT backend
+    /// and its planner are kept (transforms planned for previous
+    /// sizes stay cached by `realfft`), and only buffers depending
+    /// on the frame size are reallocated. Windows are recomputed
+    /// for the new frame size with the same functions;
+    /// [`WindowFunction::Custom`] ones can't be resized, so changing
+    /// the frame size with one set is an error, and other windows
+    /// must be set first. In low latency mode (see
+    /// [`PitchShifter::set_low_latency`]), frames are then half of
+    /// the new duration.
+    ///
+    /// This calls [`PitchShifter::reset`]. Panics on invalid
+    /// arguments; see [`PitchShifter::try_resize_window`] for a
+    /// non-panicking version.
+    pub fn resize_window(&mut self, window_duration_ms: usize) {
+        self.try_resize_window(window_duration_ms).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`PitchShifter::resize_window`], returning an error
+    /// instead of panicking on invalid arguments; the shifter is
+    /// then left untouched.
+    pub fn try_resize_window(&mut self, window_duration_ms: usize) -> Result<(), PitchShiftError> {
+        if window_duration_ms == 0 {
+            return Err(PitchShiftError::ZeroWindowDuration);
+        }
+
+        let samples = self.sample_rate * window_duration_ms / 1000;
+        if samples == 0 {
+            return Err(PitchShiftError::EmptyWindow { sample_rate: self.sample_rate });
+        }
+
+        self.try_resize_frame(samples + samples % 2, self.low_latency)
+    }
+
+    /// Trades frequency resolution for latency: frames are halved
+    /// (rounded up to an even length), and zero-padded to FFTs
+    /// twice their length (see [`PitchShifter::set_zero_padding`],
+    /// which rounds that up to a power of two if enabled).
+    ///
+    /// [`PitchShifter::latency_samples`] and the hop are halved
+    /// too: a 1024 samples frame at 16 kHz goes from 64 ms to 32 ms
+    /// of delay. Padding keeps bins as narrow as before, so
+    /// partials map to the same grid, but it only interpolates the
+    /// spectrum: as with a frame half as long, partials closer than
+    /// about `4 * sample_rate / frame_size` Hz (the main lobe of a
+    /// Hann window, twice as wide as before) blur together, which
+    /// roughens chords and low voices. Single voices and
+    /// percussive material suffer least, and also gain sharper
+    /// transients.
+    ///
+    /// [`PitchShifter::frame_size`] returns the halved size; the
+    /// [`PitchShifterConfig`] of the shifter keeps the full one.
+    /// Windows are recomputed as by [`PitchShifter::resize_window`],
+    /// and this calls [`PitchShifter::reset`]. Disabled by default.
+    ///
+    /// Panics if a [`WindowFunction::Custom`] window is set and the
+    /// frame size changes; see [`PitchShifter::try_set_low_latency`]
+    /// for a non-panicking version.
+    pub fn set_low_latency(&mut self, low_latency: bool) {
+        self.try_set_low_latency(low_latency).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`PitchShifter::set_low_latency`], returning an error
+    /// instead of panicking; the shifter is then left untouched.
+    pub fn try_set_low_latency(&mut self, low_latency: bool) -> Result<(), PitchShiftError> {
+        self.try_resize_frame(self.full_frame_size, low_latency)
+    }
+
+    /// Reallocates buffers for the frame size implied by
+    /// `full_frame_size` & `low_latency`, then resets; custom
+    /// windows can't follow a change of frame size
+    fn try_resize_frame(&mut self, full_frame_size: usize, low_latency: bool) -> Result<(), PitchShiftError> {
+        let frame_size = match low_latency {
+            true => {
+                let half = full_frame_size / 2;
+                half + half % 2
+            },
+            false => full_frame_size,
+        };
+
+        let custom = [&self.analysis_function, &self.synthesis_function]
+            .iter()
+            .any(|window| matches!(window, WindowFunction::Custom(_)));
+        if custom && frame_size != self.frame_size {
+            return Err(PitchShiftError::CustomWindowResize {
+                window_len: self.frame_size,
+                frame_size,
+            });
+        }
+
+        self.full_frame_size = full_frame_size;
+        self.low_latency = low_latency;
+        if frame_size != self.frame_size {
+            self.frame_size = frame_size;
+            self.in_fifo = vec![0.0; frame_size];
+            self.out_fifo = vec![0.0; frame_size];
+            self.output_accumulator = vec![0.0; frame_size * 2];
+            if !self.wide_accumulator.is_empty() {
+                self.wide_accumulator = vec![0.0; frame_size * 2];
+            }
+
+            self.set_analysis_window(self.analysis_function.clone());
+            self.set_synthesis_window(self.synthesis_function.clone());
         }
+
+        // reallocates spectral buffers if the FFT size changed
+        self.set_zero_padding(self.zero_padding);
+        Ok(())
+    }
+
+    /// See [`PitchShifter::new`]
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
     }
 
     /// This is where the magic happens.
@@ -104,98 +1031,2928 @@ impl PitchShifter {
     /// vice-versa.
     ///
     /// `in_b` is where the input buffer goes, and you must pass
-    /// an output buffer of the same length in `out_b`.
+    /// an output buffer of the same length in `out_b`. Samples can
+    /// have any scale (`-1.0..=1.0`, raw `i16` values as in the
+    /// `shift-wav` binary...): processing is linear, so scaling
+    /// the input scales the output by the same constant (exactly
+    /// for powers of two, up to rounding otherwise). Detection
+    /// thresholds (spectral peaks, fundamentals, de-essing) are
+    /// relative to the loudest bin of each frame; only settings
+    /// given as absolute levels, like the
+    /// [`PitchShifter::set_expander`] threshold and the
+    /// [`PitchShifter::set_output_limiter`] ceiling, depend on it.
+    ///
+    /// `over_sampling` is how many frames overlap each sample: an
+    /// integer factor, frames starting every
+    /// `frame_size / over_sampling` samples (the hop), i.e. an
+    /// overlap of `1 - 1 / over_sampling` (75% for `4`). See
+    /// [`PitchShifter::set_overlap_percent`] to think in overlaps.
+    /// It must be in `1..=frame_size`, so that hops are at least
+    /// one sample long; this panics otherwise, with a message
+    /// listing the divisors of the frame size. See
+    /// [`PitchShifter::try_shift_pitch`] for a non-panicking
+    /// version. Values which don't divide the frame size work too,
+    /// but the hop is rounded down, which makes the output slightly
+    /// louder (by `0.05` dB for 2206 samples frames and `16`).
+    ///
+    /// Buffers can have any length, including one much smaller
+    /// than the hop (`frame_size / over_sampling`), as is common in
+    /// low-latency hosts: samples are queued until a hop is complete,
+    /// and a frame is only processed when a hop boundary is crossed.
+    /// Calls which don't cross one just move samples in and out of
+    /// the FIFOs. Splitting a stream in blocks of any size produces
+    /// the same output as processing it at once.
+    ///
+    /// Silence in gives silence out: a stream starting with samples
+    /// of exactly `0.0`, after [`PitchShifter::new`] or
+    /// [`PitchShifter::reset`], comes out as exactly `0.0`, during
+    /// the latency and after it, with any shift and settings. There
+    /// is no startup transient: empty bins have no magnitude for
+    /// their phases to act on.
+    ///
+    /// `over_sampling` may change from one call to the next within
+    /// a stream: the hop in progress completes with the previous
+    /// value, and the new one applies from the next frame on. The
+    /// output stays continuous and delayed by
+    /// [`PitchShifter::frame_size`] samples; only its level can waver
+    /// slightly during the frame that overlaps both hops.
     ///
     /// Note: It's actually not magic, sadly.
     pub fn shift_pitch(&mut self, over_sampling: usize, shift: SampleReal, in_b: &[SampleReal], out_b: &mut [SampleReal]) {
-        let shift = 2.0_f32.powf(shift / 12.0);
-        let fs_real = self.frame_size as SampleReal;
-        let half_frame_size = (self.frame_size / 2) + 1;
+        let shift = self.shift_ratio(shift);
+        let len = out_b.len();
+        out_b.copy_from_slice(&in_b[..len]);
+        self.process_block(over_sampling, shift, out_b);
+    }
+
+    /// Same as [`PitchShifter::shift_pitch`], with the output written
+    /// over the input, for pipelines which don't need the input
+    /// afterward: this spares the output buffer.
+    ///
+    /// Each sample of `buffer` is read, then replaced by the output
+    /// sample of the same index, and nothing else is written; once
+    /// this returns, `buffer` holds exactly what
+    /// [`PitchShifter::shift_pitch`] would have written to `out_b`.
+    pub fn shift_pitch_in_place(&mut self, over_sampling: usize, shift: SampleReal, buffer: &mut [SampleReal]) {
+        let shift = self.shift_ratio(shift);
+        self.process_block(over_sampling, shift, buffer);
+    }
+
+    /// Same as [`PitchShifter::shift_pitch`], with the FFT scratch
+    /// space borrowed from `scratch` rather than owned by the
+    /// shifter, so that many shifters (e.g. the voices of a synth)
+    /// can share a single one.
+    ///
+    /// The shifter's own scratch space is freed on the first call.
+    /// `scratch` is only used during the call, and grown to
+    /// [`PitchShifter::scratch_len`] if it's shorter, so one buffer
+    /// fits shifters of any size once it has grown. Its content
+    /// doesn't matter, and the output is identical to that of
+    /// [`PitchShifter::shift_pitch`]. A buffer can't be used by two
+    /// calls at once: give each processing thread its own.
+    ///
+    /// Mixing this with other processing methods is fine, but
+    /// they then reallocate the shifter's scratch space.
+    pub fn process_with_scratch(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+        scratch: &mut Vec<Complex<SampleReal>>,
+    ) {
+        std::mem::swap(&mut self.fft_scratch, scratch);
+        self.shift_pitch(over_sampling, shift, in_b, out_b);
+        std::mem::swap(&mut self.fft_scratch, scratch);
+        self.fft_scratch = Vec::new();
+    }
+
+    /// Number of complex values of FFT scratch space this shifter
+    /// needs, see [`PitchShifter::process_with_scratch`]
+    pub fn scratch_len(&self) -> usize {
+        self.scratch_len
+    }
+
+    /// Same as [`PitchShifter::shift_pitch`], with `analysis` and
+    /// `synthesis` as windows for this call only, instead of those
+    /// set with [`PitchShifter::set_analysis_window`] and
+    /// [`PitchShifter::set_synthesis_window`], which are restored
+    /// afterwards.
+    ///
+    /// This is meant for experiments with windows adapted to the
+    /// signal as it goes: both must hold exactly `frame_size`
+    /// coefficients, and the level compensation for the pair is
+    /// recomputed, so any pair comes out at the usual level. Output
+    /// still overlapping from earlier frames was windowed with the
+    /// previous pair, so a change fully applies one frame later.
+    ///
+    /// Every call copies both windows and recomputes the
+    /// compensation, which costs about a sixth of processing one
+    /// frame: that's 15% more CPU with blocks of a hop, less with
+    /// longer ones, but the time is doubled by calls of an eighth of
+    /// a hop (e.g. 64 samples with 2048 samples frames and an
+    /// `over_sampling` of 4). Panics if
+    /// a window has the wrong length, see
+    /// [`PitchShifter::try_shift_pitch_with_windows`].
+    pub fn shift_pitch_with_windows(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        analysis: &[SampleReal],
+        synthesis: &[SampleReal],
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+    ) {
+        if let Err(error) = self.check_window_lengths(analysis, synthesis) {
+            panic!("{}", error);
+        }
+
+        let previous = (self.analysis_window.clone(), self.synthesis_window.clone(), self.window_compensation);
+        self.analysis_window = analysis.into();
+        self.synthesis_window = synthesis.into();
+        self.update_window_compensation();
+
+        self.shift_pitch(over_sampling, shift, in_b, out_b);
+
+        (self.analysis_window, self.synthesis_window, self.window_compensation) = previous;
+    }
+
+    /// Same as [`PitchShifter::shift_pitch_with_windows`], returning
+    /// an error instead of panicking if a window doesn't hold
+    /// `frame_size` coefficients, or for the reasons listed by
+    /// [`PitchShifter::try_shift_pitch`].
+    pub fn try_shift_pitch_with_windows(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        analysis: &[SampleReal],
+        synthesis: &[SampleReal],
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+    ) -> Result<(), PitchShiftError> {
+        self.check_over_sampling(over_sampling)?;
+        self.check_window_lengths(analysis, synthesis)?;
+
+        if in_b.len() < out_b.len() {
+            let (input_len, output_len) = (in_b.len(), out_b.len());
+            return Err(PitchShiftError::BufferTooShort { input_len, output_len });
+        }
+
+        self.shift_pitch_with_windows(over_sampling, shift, analysis, synthesis, in_b, out_b);
+        Ok(())
+    }
+
+    fn check_window_lengths(&self, analysis: &[SampleReal], synthesis: &[SampleReal]) -> Result<(), PitchShiftError> {
+        match analysis.len() == self.frame_size && synthesis.len() == self.frame_size {
+            true => Ok(()),
+            false => Err(PitchShiftError::WindowLength {
+                expected: self.frame_size,
+                analysis_len: analysis.len(),
+                synthesis_len: synthesis.len(),
+            }),
+        }
+    }
+
+    /// Same as [`PitchShifter::shift_pitch`], also writing the
+    /// unprocessed input to `dry_out`, delayed to line up with the
+    /// shifted output in `wet_out`, for mixing or routing them
+    /// externally.
+    ///
+    /// `dry_out` is the input delayed by the group delay of the
+    /// shifter, [`PitchShifter::latency_samples`]. The delay line
+    /// only sees the input of this method: for both outputs to stay
+    /// aligned, a stream must go through it from its beginning (or
+    /// from [`PitchShifter::reset`]) on. Panics if `dry_out` and
+    /// `wet_out` differ in length, or if `in_b` is shorter.
+    pub fn shift_pitch_split(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        in_b: &[SampleReal],
+        wet_out: &mut [SampleReal],
+        dry_out: &mut [SampleReal],
+    ) {
+        assert_eq!(wet_out.len(), dry_out.len(), "wet_out and dry_out must have the same length");
+        self.shift_pitch(over_sampling, shift, in_b, wet_out);
+
+        if self.dry_delay.is_empty() {
+            self.dry_delay.resize(self.frame_size, 0.0);
+        }
+
+        for (dry, sample) in dry_out.iter_mut().zip(in_b) {
+            self.dry_delay.push_back(*sample);
+            *dry = self.dry_delay.pop_front().unwrap();
+        }
+    }
 
+    /// Processes exactly one hop, `frame_size / over_sampling`
+    /// samples of `input_hop`, writing as many to the beginning of
+    /// `out` and returning how many that is.
+    ///
+    /// This is [`PitchShifter::shift_pitch`] for callers which
+    /// already chunk their stream by hop: from the beginning of a
+    /// stream (without [`PitchShifter::set_start_offset`]), each
+    /// call fills the input FIFO up to a hop boundary, processes
+    /// one frame, and reads out the hop it completed: the output is
+    /// the input of `over_sampling` calls earlier, i.e. one
+    /// frame, [`PitchShifter::latency_samples`], behind.
+    ///
+    /// Panics if `input_hop` isn't exactly one hop long or if `out`
+    /// is shorter, or on an invalid `over_sampling`.
+    pub fn process_hop(&mut self, over_sampling: usize, shift: SampleReal, input_hop: &[SampleReal], out: &mut [SampleReal]) -> usize {
+        self.expect_over_sampling(over_sampling);
         let step = self.frame_size / over_sampling;
-        let bin_frequencies = self.sample_rate as SampleReal / fs_real;
-        let expected = TAU / (over_sampling as SampleReal);
-        let fifo_latency = self.frame_size - step;
+        assert_eq!(input_hop.len(), step, "input_hop must hold exactly frame_size / over_sampling samples");
 
-        if self.overlap == 0 {
-            self.overlap = fifo_latency;
+        self.shift_pitch(over_sampling, shift, input_hop, &mut out[..step]);
+        step
+    }
+
+    /// Same as [`PitchShifter::shift_pitch`], returning an error
+    /// instead of panicking if `over_sampling` isn't in
+    /// `1..=frame_size` or if `in_b` is shorter than `out_b`.
+    pub fn try_shift_pitch(&mut self, over_sampling: usize, shift: SampleReal, in_b: &[SampleReal], out_b: &mut [SampleReal]) -> Result<(), PitchShiftError> {
+        self.check_over_sampling(over_sampling)?;
+
+        if in_b.len() < out_b.len() {
+            let (input_len, output_len) = (in_b.len(), out_b.len());
+            return Err(PitchShiftError::BufferTooShort { input_len, output_len });
         }
 
-        let pitch_weight = shift * bin_frequencies;
-        let oversamp_weight = ((over_sampling as SampleReal) / TAU) * pitch_weight;
-        let mean_expected = expected / bin_frequencies;
+        self.shift_pitch(over_sampling, shift, in_b, out_b);
+        Ok(())
+    }
 
-        for i in 0..out_b.len() {
-            self.in_fifo[self.overlap] = in_b[i];
-            out_b[i] = self.out_fifo[self.overlap - fifo_latency];
-            self.overlap += 1;
-            if self.overlap >= self.frame_size {
-                self.overlap = fifo_latency;
+    /// Retunes monophonic input so that its fundamental lands on
+    /// `target_hz`, instead of shifting it by a fixed interval.
+    ///
+    /// The fundamental of each frame is estimated from the lowest
+    /// spectral peak within 20 dB of the loudest one, refined with
+    /// its phase advance, and the frame is shifted by the ratio
+    /// bringing it to `target_hz`. Unvoiced frames (see
+    /// [`PitchShifter::last_frame_voiced`]) pass through unshifted.
+    ///
+    /// This only makes sense for input with a single fundamental
+    /// (voice, monophonic instruments); chords are retuned after
+    /// their lowest note, or erratically. Detection runs on the
+    /// same frames as shifting, so beyond the usual latency, pitch
+    /// changes are only tracked once per hop and blurred over a
+    /// frame. A weak fundamental below its harmonics can be missed,
+    /// which shifts by an octave or more. Otherwise, it behaves like
+    /// [`PitchShifter::shift_pitch`].
+    pub fn retune_to_hz(&mut self, over_sampling: usize, target_hz: SampleReal, in_b: &[SampleReal], out_b: &mut [SampleReal]) {
+        let len = out_b.len();
+        self.retune_hz = Some(target_hz);
+        out_b.copy_from_slice(&in_b[..len]);
+        self.process_block(over_sampling, 1.0, out_b);
+        self.retune_hz = None;
+    }
 
-                for k in 0..self.frame_size {
-                    self.fft_real[k] = self.in_fifo[k] * self.windowing[k];
-                }
+    /// Same as [`PitchShifter::shift_pitch`], transposing only the
+    /// partials within `band_hz` (low, high); the rest of the
+    /// spectrum is resynthesized at its original frequencies.
+    ///
+    /// Bands are converted to bins with [`PitchShifter::bin_frequency_hz`].
+    /// So that partials straddling an edge don't switch abruptly
+    /// between shifted and unshifted, bins up to two bins past each
+    /// edge are split between both, with a raised cosine crossfade.
+    /// Shifted partials can land outside of the band, where they
+    /// mix with unshifted ones like colliding partials (see
+    /// [`PitchShifter::target_bin`]), the louder one setting the
+    /// frequency of the bin. A band with `low > high` shifts
+    /// nothing.
+    pub fn shift_pitch_band(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        band_hz: (SampleReal, SampleReal),
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+    ) {
+        let len = out_b.len();
+        self.band_hz = Some(band_hz);
+        out_b.copy_from_slice(&in_b[..len]);
+        self.process_block(over_sampling, self.shift_ratio(shift), out_b);
+        self.band_hz = None;
+    }
 
-                let _ = self.forward_fft.process_with_scratch(
-                    &mut self.fft_real,
-                    &mut self.fft_cplx,
-                    &mut self.fft_scratch[..self.ffft_scratch_len],
-                );//.unwrap();
-
-                self.synthesized_magnitude.fill(0.0);
-                self.synthesized_frequency.fill(0.0);
-
-                for k in 0..half_frame_size {
-                    let k_real = k as SampleReal;
-                    let index = (k_real * shift).round() as usize;
-                    if index < half_frame_size {
-                        let (magnitude, phase) = self.fft_cplx[k].to_polar();
-                        let mut delta_phase = (phase - self.last_phase[k]) - k_real * expected;
-                        // must not round here for some reason
-                        let mut qpd = (delta_phase / PI) as i64;
-
-                        if qpd >= 0 {
-                            qpd += qpd & 1;
-                        } else {
-                            qpd -= qpd & 1;
-                        }
-
-                        delta_phase -= PI * qpd as SampleReal;
-                        self.last_phase[k] = phase;
-                        self.synthesized_magnitude[index] += magnitude;
-                        self.synthesized_frequency[index] = k_real * pitch_weight + oversamp_weight * delta_phase;
-                    }
-                }
+    /// Same as [`PitchShifter::shift_pitch`], with the shift of
+    /// each frame following the amplitude of the input (talk-box
+    /// style): `base_shift + env_depth * envelope` semitones.
+    ///
+    /// The envelope is the RMS of the frame's input, smoothed from
+    /// frame to frame by a one-pole filter with a 20 ms time
+    /// constant. It's `0.0` on silence and about `0.707` on a full
+    /// scale sine, so it stays within `0.0..=1.0` for input within
+    /// `-1.0..=1.0`; `env_depth` is then the largest deviation from
+    /// `base_shift`. It's kept between calls, and cleared by
+    /// [`PitchShifter::reset`].
+    pub fn shift_pitch_env_mod(
+        &mut self,
+        over_sampling: usize,
+        base_shift: SampleReal,
+        env_depth: SampleReal,
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+    ) {
+        let len = out_b.len();
+        self.envelope_mod = Some((base_shift, env_depth));
+        out_b.copy_from_slice(&in_b[..len]);
+        self.process_block(over_sampling, self.shift_ratio(base_shift), out_b);
+        self.envelope_mod = None;
+    }
 
-                self.fft_cplx.fill(COMPLEX_ZERO);
+    /// Same as [`PitchShifter::shift_pitch`], translating the
+    /// spectrum by `bin_shift` bins instead of scaling it.
+    ///
+    /// A pitch shift multiplies the frequency of every partial by
+    /// the same ratio, so harmonics stay at multiples of their
+    /// fundamental. Translating adds the same offset (`bin_shift`
+    /// times [`PitchShifter::bin_frequency_hz`] of bin 1) to every
+    /// partial instead, which breaks that relation: harmonic input
+    /// comes out inharmonic, with a metallic, ring-modulator like
+    /// timbre. `bin_shift` can be fractional or negative; partials
+    /// pushed below 0 Hz or past Nyquist are dropped.
+    pub fn shift_pitch_linear(&mut self, over_sampling: usize, bin_shift: SampleReal, in_b: &[SampleReal], out_b: &mut [SampleReal]) {
+        let len = out_b.len();
+        self.bin_offset = Some(bin_shift);
+        out_b.copy_from_slice(&in_b[..len]);
+        self.process_block(over_sampling, 1.0, out_b);
+        self.bin_offset = None;
+    }
 
-                for k in 0..half_frame_size {
-                    self.phase_sum[k] += mean_expected * self.synthesized_frequency[k];
+    /// Single-sideband frequency shifting: adds `hz` to the
+    /// frequency of every partial, see
+    /// [`PitchShifter::shift_pitch_linear`].
+    ///
+    /// A 200 Hz tone with harmonics at 400 and 600 Hz, shifted by
+    /// 50 Hz, becomes 250, 450 and 650 Hz: no longer multiples of
+    /// a common fundamental, which the ear hears as a clangorous,
+    /// bell-like tone rather than the same note higher. Only
+    /// positive frequencies are moved, so unlike ring modulation no
+    /// mirrored sideband appears; partials shifted below 0 Hz or
+    /// above Nyquist are dropped.
+    pub fn frequency_shift(&mut self, over_sampling: usize, hz: SampleReal, in_b: &[SampleReal], out_b: &mut [SampleReal]) {
+        let bin_shift = hz * (self.fft_size as SampleReal) / (self.sample_rate as SampleReal);
+        self.shift_pitch_linear(over_sampling, bin_shift, in_b, out_b);
+    }
 
-                    let (sin, cos) = self.phase_sum[k].sin_cos();
-                    let magnitude = self.synthesized_magnitude[k];
+    /// Same as [`PitchShifter::shift_pitch`], but instead of filling
+    /// an output buffer, calls `sink` with each output sample, in
+    /// order, which spares a buffer when samples are forwarded
+    /// elsewhere anyway (network, file writer, ring buffer...).
+    ///
+    /// `sink` is called exactly once per input sample; the output
+    /// is delayed by the usual latency, see
+    /// [`PitchShifter::latency_samples`].
+    pub fn shift_pitch_sink(&mut self, over_sampling: usize, shift: SampleReal, in_b: &[SampleReal], mut sink: impl FnMut(SampleReal)) {
+        let shift = self.shift_ratio(shift);
+        self.clip_count = 0;
+        for sample in in_b {
+            sink(self.process_sample(over_sampling, shift, *sample));
+        }
+    }
 
-                    self.fft_cplx[k].im = sin * magnitude;
-                    self.fft_cplx[k].re = cos * magnitude;
-                }
+    /// Same as [`PitchShifter::shift_pitch`], as an iterator adapter
+    /// pulling samples from `input` as output samples are requested.
+    ///
+    /// Once `input` ends, the shifter is flushed with silence until the
+    /// audio stuck in its latency is out, then the iterator ends: it
+    /// yields exactly [`PitchShifter::latency_samples`] more samples
+    /// than `input`, so that `collect()` gets the whole signal. As with
+    /// [`PitchShifter::shift_pitch`], the output begins with
+    /// that many samples of silence (fading in), which you can skip.
+    ///
+    /// The shifter is left flushed, as after [`PitchShifter::take_tail`].
+    pub fn shift_iter<I>(&mut self, over_sampling: usize, shift: SampleReal, input: I) -> ShiftedSamples<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = SampleReal>,
+    {
+        let shift = self.shift_ratio(shift);
+        self.clip_count = 0;
+        ShiftedSamples {
+            shifter: self,
+            input: input.into_iter(),
+            over_sampling,
+            shift,
+            tail: None,
+        }
+    }
 
-                let _ = self.inverse_fft.process_with_scratch(
-                    &mut self.fft_cplx,
-                    &mut self.fft_real,
-                    &mut self.fft_scratch[..self.ifft_scratch_len],
-                );//.unwrap();
+    /// Same as [`PitchShifter::shift_pitch`], for buffers of any
+    /// [`dasp_sample`] type (requires the `dasp` feature).
+    ///
+    /// Samples are converted to `f32` on their way in and back on
+    /// their way out, following [`dasp_sample`]'s conversions (e.g.
+    /// `i16::MIN..=i16::MAX` maps to `-1.0..1.0`). `f32` has a 24 bit
+    /// mantissa, so wider integer samples and `f64` are rounded to
+    /// that precision, which is below the noise of the processing
+    /// anyway.
+    #[cfg(feature = "dasp")]
+    pub fn shift_pitch_samples<S>(&mut self, over_sampling: usize, shift: SampleReal, in_b: &[S], out_b: &mut [S])
+    where
+        S: dasp_sample::Sample + dasp_sample::ToSample<SampleReal> + dasp_sample::FromSample<SampleReal>,
+    {
+        let shift = self.shift_ratio(shift);
+        self.clip_count = 0;
+        let len = out_b.len();
+        for (sample, output) in in_b[..len].iter().zip(out_b) {
+            let shifted = self.process_sample(over_sampling, shift, sample.to_sample_());
+            *output = S::from_sample_(shifted);
+        }
+    }
 
-                let acc_oversamp: SampleReal = 2.0 / (half_frame_size * over_sampling) as SampleReal;
+    /// Same as [`PitchShifter::shift_pitch`], as an adapter of a
+    /// mono [`dasp_signal::Signal`] (requires the `dasp` feature).
+    ///
+    /// Frames are converted like in [`PitchShifter::shift_pitch_samples`].
+    /// The output is delayed by [`PitchShifter::latency_samples`],
+    /// and the adapter is exhausted when `signal` is: unlike
+    /// [`PitchShifter::shift_iter`], it doesn't flush the tail.
+    #[cfg(feature = "dasp")]
+    pub fn shift_signal<S>(&mut self, over_sampling: usize, shift: SampleReal, signal: S) -> ShiftedSignal<'_, S>
+    where
+        S: dasp_signal::Signal,
+        S::Frame: dasp_frame::Frame<NumChannels = dasp_frame::N1>,
+    {
+        let shift = self.shift_ratio(shift);
+        self.clip_count = 0;
+        ShiftedSignal {
+            shifter: self,
+            signal,
+            over_sampling,
+            shift,
+        }
+    }
 
-                for k in 0..self.frame_size {
-                    let product = self.windowing[k] * self.fft_real[k] * acc_oversamp;
-                    self.output_accumulator[k] += product / 2.0;
-                }
+    /// Same as [`PitchShifter::shift_pitch`], but calls
+    /// `progress(samples_done, total)` along the way, so that long
+    /// offline renders can report progress.
+    ///
+    /// `progress` is called after each processed frame, i.e. every
+    /// `frame_size / over_sampling` samples, and once more with
+    /// `samples_done == total` when the buffer is done. That can be
+    /// hundreds of times per second of audio: throttle your UI
+    /// updates accordingly.
+    pub fn shift_pitch_with_progress(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+        mut progress: impl FnMut(usize, usize),
+    ) {
+        let shift = self.shift_ratio(shift);
+        self.clip_count = 0;
+        let total = out_b.len();
+        for i in 0..total {
+            let frames = self.frames;
+            out_b[i] = self.process_sample(over_sampling, shift, in_b[i]);
+            if self.frames != frames && i + 1 < total {
+                progress(i + 1, total);
+            }
+        }
+        progress(total, total);
+    }
 
-                self.out_fifo[..step].copy_from_slice(&self.output_accumulator[..step]);
-                self.output_accumulator.copy_within(step..(step + self.frame_size), 0);
-                self.in_fifo.copy_within(step..(step + fifo_latency), 0);
+    /// Same as [`PitchShifter::shift_pitch`], but also calls
+    /// `on_frame` with the magnitudes of each analyzed frame, e.g.
+    /// to draw a spectrogram without a second FFT.
+    ///
+    /// `on_frame` is called at each frame boundary, i.e. every
+    /// `frame_size / over_sampling` samples, right after the frame
+    /// is analyzed. It gets [`PitchShifter::fft_size`]` / 2 + 1`
+    /// magnitudes, from DC to Nyquist (see
+    /// [`PitchShifter::bin_frequency_hz`]), as computed by the
+    /// forward FFT of the windowed frame, i.e. before shifting and
+    /// scaled by the backend's forward normalization. Frames end
+    /// [`PitchShifter::latency_samples`] before the output samples
+    /// they produce. [`PitchShifter::set_bypass_on_unity`] is
+    /// ignored here, since bypassed frames aren't analyzed.
+    pub fn shift_pitch_with_spectrogram(
+        &mut self,
+        over_sampling: usize,
+        shift: SampleReal,
+        in_b: &[SampleReal],
+        out_b: &mut [SampleReal],
+        mut on_frame: impl FnMut(&[SampleReal]),
+    ) {
+        let shift = self.shift_ratio(shift);
+        self.clip_count = 0;
+        self.spectrogram = Some(std::mem::take(&mut self.spectrogram_buffer));
+        let len = out_b.len();
+        for (sample, output) in in_b[..len].iter().zip(out_b) {
+            let frames = self.frames;
+            *output = self.process_sample(over_sampling, shift, *sample);
+            if let Some(magnitudes) = self.spectrogram.as_ref().filter(|_| self.frames != frames) {
+                on_frame(magnitudes);
             }
         }
+        self.spectrogram_buffer = self.spectrogram.take().unwrap_or_default();
     }
-}
\ No newline at end of file
+
+    /// Applies the vocoder's pitch-shift to a single spectrum,
+    /// without any FFT or windowing, for integration in an
+    /// external STFT pipeline.
+    ///
+    /// Both slices must contain `fft_size / 2 + 1` bins, ordered
+    /// from DC to Nyquist, as produced by a real-to-complex FFT of
+    /// a [`PitchShifter::fft_size`]-long frame (which is the frame
+    /// size, unless [`PitchShifter::set_zero_padding`] is enabled).
+    /// Consecutive calls must be given consecutive frames, spaced
+    /// by `frame_size / over_sampling` samples, where
+    /// `over_sampling` is the one given to
+    /// [`PitchShifter::set_over_sampling`]: the phase of each bin is
+    /// compared to the previous frame's to measure its true
+    /// frequency.
+    ///
+    /// `shift` is in semitones, as in [`PitchShifter::shift_pitch`].
+    pub fn shift_frame(&mut self, shift: SampleReal, in_spectrum: &[Complex<SampleReal>], out_spectrum: &mut [Complex<SampleReal>]) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        assert_eq!(in_spectrum.len(), half_fft_size, "in_spectrum must have fft_size / 2 + 1 bins");
+        assert_eq!(out_spectrum.len(), half_fft_size, "out_spectrum must have fft_size / 2 + 1 bins");
+
+        let shift = self.shift_ratio(shift);
+        self.fft_cplx.copy_from_slice(in_spectrum);
+        self.process_spectrum(self.over_sampling, shift);
+        out_spectrum.copy_from_slice(&self.fft_cplx);
+    }
+
+    /// Same as [`PitchShifter::shift_frame`], with spectra in split
+    /// (planar) layout: `in_re` and `in_im` hold the real and
+    /// imaginary parts of the bins, and the shifted ones are written
+    /// to `out_re` and `out_im`.
+    ///
+    /// The bins are converted to and from the interleaved layout used
+    /// internally (see [`planar_to_complex`]) without allocating,
+    /// and the output is the same as that of
+    /// [`PitchShifter::shift_frame`]. Panics if any slice doesn't
+    /// have `fft_size / 2 + 1` values.
+    pub fn shift_frame_planar(
+        &mut self,
+        shift: SampleReal,
+        in_re: &[SampleReal],
+        in_im: &[SampleReal],
+        out_re: &mut [SampleReal],
+        out_im: &mut [SampleReal],
+    ) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        assert_eq!(in_re.len(), half_fft_size, "in_re must have fft_size / 2 + 1 bins");
+        assert_eq!(out_re.len(), half_fft_size, "out_re must have fft_size / 2 + 1 bins");
+
+        let shift = self.shift_ratio(shift);
+        planar_to_complex(in_re, in_im, &mut self.fft_cplx);
+        self.process_spectrum(self.over_sampling, shift);
+        complex_to_planar(&self.fft_cplx, out_re, out_im);
+    }
+
+    /// Resynthesizes one hop of audio from the magnitude and true
+    /// frequency (in Hz) of each bin, for custom spectral processing:
+    /// phases are accumulated from the frequencies, then the frame
+    /// goes through the inverse FFT, the synthesis window and the
+    /// overlap-add, as in [`PitchShifter::shift_pitch`].
+    ///
+    /// `magnitudes` and `frequencies` must contain `fft_size / 2 + 1`
+    /// bins, ordered from DC to Nyquist, with magnitudes on the scale
+    /// of the forward FFT of a windowed frame; `out_frame` receives
+    /// the `frame_size / over_sampling` samples completed by this
+    /// frame, `over_sampling` being the one given to
+    /// [`PitchShifter::set_over_sampling`]. Each call advances the
+    /// phase of every bin by one hop, so consecutive calls must be
+    /// given consecutive frames; the first frames fade in, like the
+    /// beginning of any stream. This shares its state with the
+    /// other processing methods: don't interleave them.
+    ///
+    /// Panics if any slice has the wrong length.
+    pub fn synthesize_frame(&mut self, magnitudes: &[SampleReal], frequencies: &[SampleReal], out_frame: &mut [SampleReal]) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let step = self.frame_size / self.over_sampling;
+        assert_eq!(magnitudes.len(), half_fft_size, "magnitudes must have fft_size / 2 + 1 bins");
+        assert_eq!(frequencies.len(), half_fft_size, "frequencies must have fft_size / 2 + 1 bins");
+        assert_eq!(out_frame.len(), step, "out_frame must have frame_size / over_sampling samples");
+
+        self.synthesized_magnitude.copy_from_slice(magnitudes);
+        self.synthesized_frequency.copy_from_slice(frequencies);
+        self.resynthesize(self.over_sampling);
+        self.overlap_add(self.over_sampling, step);
+        out_frame.copy_from_slice(&self.out_fifo[..step]);
+    }
+
+    /// Flushes the shifter and returns the end of the audio fed so
+    /// far, which is otherwise stuck in its latency.
+    ///
+    /// This feeds `frame_size` samples of silence using the
+    /// `over_sampling` and shift of the last processed sample, so
+    /// the returned tail is exactly [`PitchShifter::frame_size`]
+    /// samples long. The shifter is then drained: it can keep
+    /// going, but there will be silence where the tail was.
+    ///
+    /// To render one long file as independent adjacent chunks
+    /// (possibly in parallel), process each chunk with a fresh
+    /// shifter, take the tail of chunk `n` and give it to
+    /// [`PitchShifter::prepend_tail`] on the shifter of chunk `n + 1`
+    /// before processing it, then concatenate the outputs of all
+    /// chunks. The shifter of the last chunk must be flushed too.
+    ///
+    /// This removes the gap otherwise heard at each boundary, but
+    /// it's not identical to a single continuous render: frames
+    /// spanning the boundary are analyzed separately for each
+    /// side, and both shifters accumulate phases independently,
+    /// so the two sides can partially interfere over one frame.
+    pub fn take_tail(&mut self) -> Vec<SampleReal> {
+        let over_sampling = self.last_over_sampling;
+        let shift = self.last_shift;
+        self.clip_count = 0;
+        (0..self.frame_size)
+            .map(|_| self.process_sample(over_sampling, shift, 0.0))
+            .collect()
+    }
+
+    /// Mixes `tail` into the next output samples, so that they
+    /// continue the output of a previous, independent render.
+    ///
+    /// See [`PitchShifter::take_tail`] for how to stitch chunks.
+    /// Several tails can be given; they are mixed in together.
+    pub fn prepend_tail(&mut self, tail: &[SampleReal]) {
+        if self.carry.len() < tail.len() {
+            self.carry.resize(tail.len(), 0.0);
+        }
+
+        for (carried, sample) in self.carry.iter_mut().zip(tail) {
+            *carried += sample;
+        }
+    }
+
+    /// Measures the shift, in semitones, that this configuration
+    /// actually applies when asked for `requested_shift`.
+    ///
+    /// A 440 Hz sine is processed and the fundamental of the output
+    /// is estimated from its zero crossings, over at least half a
+    /// second. Mapping partials to whole bins can make the realized
+    /// shift differ slightly from the requested one; larger frames
+    /// (finer bins) and higher `over_sampling` generally improve
+    /// accuracy.
+    ///
+    /// This processes audio, so it calls [`PitchShifter::reset`]:
+    /// call it before processing, not in the middle of a stream.
+    pub fn measure_shift(&mut self, over_sampling: usize, requested_shift: SampleReal) -> SampleReal {
+        let test_hz = 440.0;
+        let sr_real = self.sample_rate as SampleReal;
+        // skip the latency & let phases settle for a few frames
+        let warm_up = self.frame_size * 2;
+        // a fraction of a period is lost at each end of the measure,
+        // which is only negligible over many of them
+        let measured = (self.frame_size * 8).max(self.sample_rate / 2);
+        let len = warm_up + measured;
+
+        let in_b: Vec<SampleReal> = (0..len)
+            .map(|i| (TAU * test_hz * (i as SampleReal) / sr_real).sin())
+            .collect();
+        let mut out_b = vec![0.0; len];
+
+        self.reset();
+        self.shift_pitch(over_sampling, requested_shift, &in_b, &mut out_b);
+        self.reset();
+
+        match zero_crossing_frequency(&out_b[warm_up..], sr_real) {
+            Some(measured_hz) => pitch::ratio_to_semitones(measured_hz / test_hz),
+            None => SampleReal::NAN,
+        }
+    }
+
+    /// Smallest `over_sampling` (a power of two) shifting `in_b` by
+    /// `shift` semitones with an error below `max_error_db`.
+    ///
+    /// `in_b` should be representative of the material to process,
+    /// as artifacts depend on it as much as on the shift. It's
+    /// rendered with each candidate, using the settings of this
+    /// shifter, and compared to a reference rendered with the
+    /// largest one, 64 (or less for frames shorter than that). The
+    /// error is the energy of their difference relative to that of
+    /// the reference, in dB, with the levels matched first (they
+    /// differ slightly for hops which don't divide the frame size)
+    /// and a frame trimmed at both ends. Too few overlapping frames
+    /// smear the phases of partials with each other: with Hann
+    /// windows, 4 is usually enough for small shifts at -40 dB,
+    /// while octave shifts need 8 or more.
+    /// The reference is returned if no smaller candidate qualifies.
+    ///
+    /// This processes `in_b` several times, so it calls
+    /// [`PitchShifter::reset`]: call it before processing, not in
+    /// the middle of a stream.
+    pub fn recommend_over_sampling(&mut self, shift: SampleReal, in_b: &[SampleReal], max_error_db: SampleReal) -> usize {
+        let largest = 1 << self.frame_size.min(64).ilog2();
+        let reference = self.process_clip(largest, shift, in_b);
+
+        // frames at both ends hold fading partials
+        let trim = match in_b.len() > 2 * self.frame_size {
+            true => self.frame_size..(in_b.len() - self.frame_size),
+            false => 0..in_b.len(),
+        };
+        let reference = &reference[trim.clone()];
+        let reference_energy: SampleReal = reference.iter().map(|s| s * s).sum();
+
+        let mut over_sampling = 1;
+        while over_sampling < largest {
+            let output = self.process_clip(over_sampling, shift, in_b);
+            let output = &output[trim.clone()];
+
+            let output_energy: SampleReal = output.iter().map(|s| s * s).sum();
+            let correlation: SampleReal = output.iter().zip(reference).map(|(o, r)| o * r).sum();
+            let gain = match output_energy > 0.0 {
+                true => correlation / output_energy,
+                false => 0.0,
+            };
+
+            let error: SampleReal = output.iter().zip(reference).map(|(o, r)| (gain * o - r).powi(2)).sum();
+            if error <= reference_energy * db_to_gain(max_error_db).powi(2) {
+                return over_sampling;
+            }
+
+            over_sampling *= 2;
+        }
+
+        largest
+    }
+
+    /// When enabled, the error between the requested shift and the
+    /// one realized by [`PitchShifter::measure_shift`] is corrected
+    /// by scaling the frequency ratio of each frame accordingly.
+    /// This has the effect of a fractional resampling of the output
+    /// by the residual error, without its interpolation or a change
+    /// of length. Synthesis follows the measured frequencies of
+    /// partials rather than bin centers, so this error is usually a
+    /// fraction of a cent; it reaches about a cent with frames of a
+    /// dozen samples, where partials collide in few bins. The
+    /// realized shift isn't quite linear in the applied one, so the
+    /// correction is refined over three measures, after which
+    /// [`PitchShifter::measure_shift`] agrees with the request to a
+    /// few hundredths of a cent. With an `over_sampling` of 2, phases
+    /// are too ambiguous for the measure to generalize.
+    ///
+    /// The error is measured for the shift and `over_sampling` set
+    /// with [`PitchShifter::set_shift`] and
+    /// [`PitchShifter::set_over_sampling`], when this is enabled
+    /// and again whenever they, the windows, the frame size or the
+    /// [`BinMapping`] change: each measure shifts half a second of
+    /// a sine in a separate shifter built for the occasion, which
+    /// allocates and takes about as long as processing that much
+    /// audio (three times over), so call these setters outside of
+    /// the audio thread.
+    /// Processing itself costs nothing more, but only frames with
+    /// the measured shift & `over_sampling` are corrected; others
+    /// (automation, [`PitchShifter::shift_pitch_env_mod`], shifts
+    /// from a [`RealtimeParameters`] handle, ...) are left as is.
+    /// Other spectral settings aren't applied to the measure.
+    /// Disabled by default.
+    pub fn set_fine_tune(&mut self, enabled: bool) {
+        self.fine_tune = enabled;
+        self.update_fine_tune();
+    }
+
+    /// Measures the fine tune correction for the current settings
+    fn update_fine_tune(&mut self) {
+        if !self.fine_tune {
+            self.fine_tune_correction = None;
+            return;
+        }
+
+        let mut probe = PitchShifter::from_config(&self.config());
+        probe.set_bin_mapping(self.bin_mapping);
+        let requested = self.quantization.apply(self.shift);
+
+        // the realized shift isn't quite linear in the applied one
+        let mut applied = requested;
+        for _ in 0..FINE_TUNE_ITERATIONS {
+            let realized = probe.measure_shift(self.over_sampling, applied);
+            if !realized.is_finite() {
+                break;
+            }
+            applied += requested - realized;
+        }
+        let correction = pitch::semitones_to_ratio(applied - requested);
+
+        let ratio = self.shift_ratio(self.shift);
+        self.fine_tune_correction = Some((self.over_sampling, ratio, correction));
+    }
+
+    /// Frequency ratio to apply instead of `shift` with fine tuning
+    fn fine_tune_ratio(&self, over_sampling: usize, shift: SampleReal) -> SampleReal {
+        match self.fine_tune_correction {
+            Some((os, ratio, correction)) if (os, ratio) == (over_sampling, shift) => shift * correction,
+            _ => shift,
+        }
+    }
+
+    /// Shifts a whole clip at once and returns the result, aligned
+    /// with the input and of the same length.
+    ///
+    /// The shifter is reset, then fed `in_b` followed by enough
+    /// silence to flush it, and the leading latency is trimmed, so
+    /// the output is directly usable. It's reset again afterward,
+    /// so clips processed in a row don't bleed into each other.
+    ///
+    /// The latency is trimmed, not the startup of the vocoder:
+    /// phases take a few frames to settle (see
+    /// [`PitchShifter::set_first_frame_phase_reset`]), so the level
+    /// of the first hop (`frame_size / over_sampling` samples) can
+    /// be off by up to a third, while it's within a few percent
+    /// from the second one on. Priming the shifter with a
+    /// time-reversed copy of the beginning doesn't help there:
+    /// reversed partials leave neighbouring bins with opposed
+    /// phases, which makes the start worse and can attenuate them
+    /// for the whole clip.
+    ///
+    /// See [`PitchShifter::set_reverse_processing`] to process the
+    /// clip backward.
+    pub fn process_clip(&mut self, over_sampling: usize, shift: SampleReal, in_b: &[SampleReal]) -> Vec<SampleReal> {
+        if self.reverse_processing {
+            let reversed: Vec<SampleReal> = in_b.iter().rev().copied().collect();
+            let mut out_b = self.render_clip(over_sampling, shift, &reversed);
+            out_b.reverse();
+            return out_b;
+        }
+
+        self.render_clip(over_sampling, shift, in_b)
+    }
+
+    /// [`PitchShifter::process_clip`], forward
+    fn render_clip(&mut self, over_sampling: usize, shift: SampleReal, in_b: &[SampleReal]) -> Vec<SampleReal> {
+        let latency = self.latency_samples(over_sampling);
+        let mut padded = Vec::with_capacity(in_b.len() + latency);
+        padded.extend_from_slice(in_b);
+        padded.resize(in_b.len() + latency, 0.0);
+        let mut out_b = vec![0.0; padded.len()];
+
+        self.reset();
+        self.shift_pitch(over_sampling, shift, &padded, &mut out_b);
+        self.reset();
+
+        out_b.drain(..latency);
+        out_b
+    }
+
+    /// Changes the duration of the input by `ratio` without changing
+    /// its pitch: `2.0` makes it twice as long, `0.5` twice as short.
+    ///
+    /// Returns how many samples were written to the beginning of
+    /// `out_b`; the rest of it is left untouched. Each call produces
+    /// about `in_b.len() * ratio` samples, but not exactly: input
+    /// samples are kept until the ones following them arrive, and
+    /// if `out_b` is too short, the input it couldn't hold is kept
+    /// for the next call, so nothing is lost. Passing an empty `in_b`
+    /// then retrieves it. The output is delayed by
+    /// [`PitchShifter::latency_samples`], counted in output samples.
+    ///
+    /// The input is resampled by linear interpolation (which slows
+    /// it down and lowers its pitch by `ratio`), then shifted back up
+    /// by `ratio`. Stretching is thus clean, but compressing aliases
+    /// partials above `ratio * sample_rate / 2`. Keep the same `ratio`
+    /// during a stream; interleaving this with other processing
+    /// methods isn't supported.
+    ///
+    /// Panics if `ratio` isn't strictly positive.
+    pub fn time_stretch(&mut self, over_sampling: usize, ratio: SampleReal, in_b: &[SampleReal], out_b: &mut [SampleReal]) -> usize {
+        assert!(ratio > 0.0, "time stretch ratio must be greater than zero");
+        self.stretch_input.extend(in_b);
+        self.clip_count = 0;
+        let step = 1.0 / (ratio as f64);
+
+        // positions are computed from the stream's beginning, so
+        // that they don't depend on how it's split in calls
+        let mut written = 0;
+        while written < out_b.len() {
+            let position = self.stretch_outputs as f64 * step;
+            let index = (position as u64 - self.stretch_consumed) as usize;
+            if index + 1 >= self.stretch_input.len() {
+                break;
+            }
+
+            let (a, b) = (self.stretch_input[index], self.stretch_input[index + 1]);
+            let fraction = position.fract() as SampleReal;
+            out_b[written] = self.process_sample(over_sampling, ratio, a + (b - a) * fraction);
+            self.stretch_outputs += 1;
+            written += 1;
+        }
+
+        let next = (self.stretch_outputs as f64 * step) as u64 - self.stretch_consumed;
+        let consumed = (next as usize).min(self.stretch_input.len());
+        self.stretch_input.drain(..consumed);
+        self.stretch_consumed += consumed as u64;
+
+        written
+    }
+
+    /// Number of output samples needed to hold [`PitchShifter::shift_pitch`]'s
+    /// output for `input_length` input samples, flushed tail
+    /// included.
+    ///
+    /// Each call to `shift_pitch` writes exactly as many samples as
+    /// it's given, but the output is delayed by
+    /// [`PitchShifter::latency_samples`]: the processed input only
+    /// fully comes out after that many more samples (e.g. of
+    /// silence), which this counts. [`PitchShifter::process_clip`]
+    /// processes that many, then trims the leading latency.
+    pub fn output_length(&self, input_length: usize, over_sampling: usize) -> usize {
+        input_length + self.latency_samples(over_sampling)
+    }
+
+    /// Same as [`PitchShifter::output_length`], for
+    /// [`PitchShifter::time_stretch`] by `ratio`.
+    ///
+    /// Without the tail, this is exactly the total that calls to
+    /// `time_stretch` return for a stream of `input_length` samples
+    /// (with room in `out_b`), however the stream is split; the
+    /// last input sample is only used once the next one is given,
+    /// so it's about `(input_length - 1) * ratio`. The tail holds
+    /// [`PitchShifter::latency_samples`] more output samples, which
+    /// take about `latency / ratio` more input samples to flush.
+    ///
+    /// Panics if `ratio` isn't strictly positive.
+    pub fn stretch_output_length(&self, input_length: usize, ratio: SampleReal, over_sampling: usize) -> usize {
+        assert!(ratio > 0.0, "time stretch ratio must be greater than zero");
+        let step = 1.0 / (ratio as f64);
+        let input_length = input_length as u64;
+        // the k-th output sample needs input samples up to
+        // `floor(k * step) + 1`
+        let available = |k: u64| (k as f64 * step) as u64 + 1 < input_length;
+
+        let mut outputs = match input_length {
+            0 | 1 => 0,
+            _ => ((input_length - 1) as f64 / step) as u64,
+        };
+        while outputs > 0 && !available(outputs - 1) {
+            outputs -= 1;
+        }
+        while available(outputs) {
+            outputs += 1;
+        }
+
+        outputs as usize + self.latency_samples(over_sampling)
+    }
+
+    /// Sets the `over_sampling` value used by [`PitchShifter::push`]
+    /// and [`PitchShifter::shift_frame`].
+    ///
+    /// See [`PitchShifter::shift_pitch`] for its meaning; the
+    /// default is `16`. Panics if it isn't in `1..=frame_size`.
+    pub fn set_over_sampling(&mut self, over_sampling: usize) {
+        self.expect_over_sampling(over_sampling);
+        self.over_sampling = over_sampling;
+        self.update_fine_tune();
+    }
+
+    fn check_over_sampling(&self, over_sampling: usize) -> Result<(), PitchShiftError> {
+        match over_sampling == 0 || over_sampling > self.frame_size {
+            true => Err(PitchShiftError::InvalidOverSampling { over_sampling, frame_size: self.frame_size }),
+            false => Ok(()),
+        }
+    }
+
+    fn expect_over_sampling(&self, over_sampling: usize) {
+        if let Err(error) = self.check_over_sampling(over_sampling) {
+            panic!("{}", error);
+        }
+    }
+
+    /// Same as [`PitchShifter::set_over_sampling`], with the overlap
+    /// between consecutive frames given as a percentage of the frame:
+    ///
+    /// | overlap | `over_sampling` |
+    /// |---------|-----------------|
+    /// | 50%     | 2               |
+    /// | 75%     | 4               |
+    /// | 87.5%   | 8               |
+    /// | 93.75%  | 16              |
+    /// | 96.875% | 32              |
+    ///
+    /// i.e. `over_sampling = 100 / (100 - percent)`. Only values
+    /// dividing [`PitchShifter::frame_size`] are considered, so that
+    /// hops are whole: if none gives exactly `percent`, nothing is
+    /// changed and [`PitchShiftError::InexactOverlap`] suggests the
+    /// nearest one. `percent` must be in `0.0..100.0`.
+    pub fn set_overlap_percent(&mut self, percent: SampleReal) -> Result<(), PitchShiftError> {
+        if !(0.0..100.0).contains(&percent) {
+            return Err(PitchShiftError::InvalidOverlap { percent });
+        }
+
+        let overlap = |over_sampling: usize| 100.0 - 100.0 / (over_sampling as SampleReal);
+        let distance = |over_sampling: usize| (overlap(over_sampling) - percent).abs();
+        let nearest = (1..=self.frame_size)
+            .filter(|over_sampling| self.frame_size.is_multiple_of(*over_sampling))
+            .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+            .unwrap_or(1);
+
+        // tolerate rounding in the given percentage
+        if distance(nearest) > 1e-3 {
+            return Err(PitchShiftError::InexactOverlap {
+                percent,
+                nearest_over_sampling: nearest,
+                nearest_percent: overlap(nearest),
+            });
+        }
+
+        self.over_sampling = nearest;
+        self.update_fine_tune();
+        Ok(())
+    }
+
+    /// The `over_sampling` value used by [`PitchShifter::push`] and
+    /// [`PitchShifter::shift_frame`], which can also be passed to
+    /// [`PitchShifter::shift_pitch`] to follow [`PitchShifter::set_quality`].
+    pub fn over_sampling(&self) -> usize {
+        self.over_sampling
+    }
+
+    /// Picks a sensible combination of settings for the given
+    /// quality level; this is a shortcut to these individual
+    /// settings, which can still be adjusted afterward:
+    ///
+    /// | [`Quality`] | `over_sampling` | [`BinMapping`] |
+    /// |-------------|-----------------|----------------|
+    /// | `Draft`     | 4               | `Round`        |
+    /// | `Normal`    | 8               | `Round`        |
+    /// | `High`      | 16              | `Interpolate`  |
+    /// | `Ultra`     | 32              | `Interpolate`  |
+    ///
+    /// Processing cost is roughly proportional to `over_sampling`.
+    /// The `over_sampling` set here applies to methods which don't
+    /// take one as argument; for the others, pass them
+    /// [`PitchShifter::over_sampling`].
+    pub fn set_quality(&mut self, quality: Quality) {
+        let (over_sampling, bin_mapping) = match quality {
+            Quality::Draft => (4, BinMapping::Round),
+            Quality::Normal => (8, BinMapping::Round),
+            Quality::High => (16, BinMapping::Interpolate),
+            Quality::Ultra => (32, BinMapping::Interpolate),
+        };
+
+        self.over_sampling = over_sampling;
+        self.bin_mapping = bin_mapping;
+        self.update_fine_tune();
+    }
+
+    /// Snaps every requested shift (the `shift` arguments of the
+    /// processing methods, and [`PitchShifter::set_shift`]) to whole
+    /// semitones, or to the degrees of a scale, see [`QuantMode`]. This
+    /// makes continuous shift automation move in steps, like MIDI
+    /// notes, e.g. for harmonizer presets.
+    ///
+    /// This quantizes the shift itself, whatever the input: unlike
+    /// auto-tune, it doesn't detect the pitch of the input, so
+    /// out-of-tune input stays out of tune, by steps of the scale.
+    /// See [`PitchShifter::retune_to_hz`] for retuning to a pitch.
+    pub fn set_shift_quantization(&mut self, mode: QuantMode) {
+        self.quantization = mode;
+        self.update_fine_tune();
+    }
+
+    /// Frequency ratio for a requested `shift`, in semitones
+    fn shift_ratio(&self, shift: SampleReal) -> SampleReal {
+        pitch::semitones_to_ratio(self.quantization.apply(shift))
+    }
+
+    /// Sets the shift, in semitones, used by [`PitchShifter::push`].
+    ///
+    /// The default is `0.0`.
+    pub fn set_shift(&mut self, shift: SampleReal) {
+        self.shift = shift;
+        self.update_fine_tune();
+    }
+
+    /// Feeds samples to the shifter, using the settings given to
+    /// [`PitchShifter::set_over_sampling`] & [`PitchShifter::set_shift`].
+    ///
+    /// Every input sample produces exactly one output sample,
+    /// which is queued in an internal ring buffer until you
+    /// retrieve it with [`PitchShifter::pull`]. This allows you
+    /// to feed and retrieve audio in blocks of unrelated sizes,
+    /// but both streams keep the same rate: over time, as many
+    /// samples are pulled as were pushed. To produce more or fewer
+    /// samples than the input holds, see
+    /// [`PitchShifter::time_stretch`].
+    ///
+    /// The queue grows as needed: if you never pull, it will
+    /// keep every processed sample in memory.
+    pub fn push(&mut self, input: &[SampleReal]) {
+        let mut semitones = self.shift;
+        let mut shift = self.shift_ratio(semitones);
+        self.pending.reserve(input.len());
+        self.clip_count = 0;
+        for sample in input {
+            // may have been changed through `realtime_parameters`
+            if self.shift != semitones {
+                semitones = self.shift;
+                shift = self.shift_ratio(semitones);
+            }
+
+            let output = self.process_sample(self.over_sampling, shift, *sample);
+            self.pending.push_back(output);
+        }
+    }
+
+    /// Retrieves processed samples queued by [`PitchShifter::push`].
+    ///
+    /// Returns how many samples were written to the beginning of
+    /// `out`, which is the smallest of `out.len()` and the number
+    /// of samples waiting in the queue. The rest of `out` is left
+    /// untouched.
+    ///
+    /// As with [`PitchShifter::shift_pitch`], the output stream is
+    /// delayed with regard to the input: its first `frame_size`
+    /// samples are silent while the analysis windows fill up.
+    pub fn pull(&mut self, out: &mut [SampleReal]) -> usize {
+        let len = out.len().min(self.pending.len());
+        for (dst, src) in out.iter_mut().zip(self.pending.drain(..len)) {
+            *dst = src;
+        }
+        len
+    }
+
+    /// Number of processed samples waiting to be retrieved
+    /// with [`PitchShifter::pull`].
+    pub fn available(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Removes DC offset and sub-audio rumble from the input with
+    /// a high-pass filter at `cutoff_hz`, before analysis: left in,
+    /// they muddy the phase estimates of the lowest bins.
+    ///
+    /// This is a gentle one-pole filter (-6 dB per octave below
+    /// the cutoff), not a brickwall: something around `20.0` removes
+    /// rumble without audibly thinning the low end. It applies to
+    /// what is analyzed (and bypassed, see
+    /// [`PitchShifter::set_bypass_on_unity`]) only, not to any dry
+    /// signal you mix with the output yourself. A cutoff of `0.0`
+    /// or less disables it, which is the default.
+    pub fn set_highpass_hz(&mut self, cutoff_hz: SampleReal) {
+        self.highpass = (cutoff_hz > 0.0).then(|| HighPass::new(cutoff_hz, self.sample_rate));
+    }
+
+    /// Pre-emphasizes the input before analysis, with the one-pole
+    /// filter `y[n] = x[n] - coeff * x[n - 1]`, and de-emphasizes
+    /// the output with its inverse, `1 / (1 - coeff * z^-1)`.
+    ///
+    /// Tilting the analyzed frames up (by about 6 dB per octave,
+    /// from `1.0 - coeff` at DC to `1.0 + coeff` at Nyquist) keeps
+    /// loud low partials from leaking over quieter high ones, which
+    /// then get cleaner phase estimates: this is mostly useful for
+    /// speech and other material whose highs are much quieter than
+    /// its lows. Something around `0.95` is common.
+    ///
+    /// The de-emphasis is level-compensating: it's applied to each
+    /// bin's magnitude before the bins are moved, with the exact
+    /// inverse gain at its analyzed frequency, so the spectral
+    /// balance is the same as without emphasis at every shift (a
+    /// time domain de-emphasis after shifting would tilt it by up to
+    /// 6 dB per octave of shift). Bypassed frames (see
+    /// [`PitchShifter::set_bypass_on_unity`]) are processed as usual
+    /// while this is enabled. `coeff` is capped at `0.999`, and
+    /// `0.0` or less disables the emphasis, which is the default.
+    pub fn set_emphasis(&mut self, coeff: SampleReal) {
+        self.emphasis = (coeff > 0.0).then(|| Emphasis::new(coeff.min(0.999), self.fft_size));
+    }
+
+    /// When enabled, a `shift` of exactly `0.0` skips the FFTs
+    /// entirely: each frame is overlap-added as it was windowed,
+    /// instead of being analyzed and resynthesized, which gives
+    /// the same delay and level as processed audio.
+    ///
+    /// This saves a lot of CPU when automating the shift through
+    /// zero. Going in and out of bypass crossfades over a frame,
+    /// without clicks, and processing resumes from the phases of
+    /// the input; the output only differs slightly from what the
+    /// vocoder produces at zero. Frames aren't bypassed while any
+    /// setting alters their spectrum (de-essing, tilt, expander,
+    /// smoothing, frame gain normalization, ...), while settings
+    /// acting on output samples (limiter, auto gain) still apply.
+    ///
+    /// Disabled by default.
+    pub fn set_bypass_on_unity(&mut self, bypass: bool) {
+        self.bypass_on_unity = bypass;
+    }
+
+    /// Enables a soft limiter on the output, which keeps samples
+    /// within `-ceiling..=ceiling`, e.g. to avoid clipping when
+    /// rendering a loud shifted signal to an integer format.
+    ///
+    /// Samples below half the ceiling are untouched; above that,
+    /// they're bent smoothly (tanh-style) towards the ceiling. It's
+    /// applied sample by sample on the (delayed) output stream,
+    /// without lookahead: a safety net against overs, not a
+    /// mastering-grade limiter. A ceiling of `0.0` or less, or a
+    /// non-finite one, disables it, which is the default.
+    pub fn set_output_limiter(&mut self, ceiling: SampleReal) {
+        self.limiter_ceiling = (ceiling > 0.0 && ceiling.is_finite()).then_some(ceiling);
+    }
+
+    /// Sets the level above which output samples count as clipped,
+    /// see [`PitchShifter::last_output_clip_count`]. The default is
+    /// `1.0`, full scale for float audio.
+    ///
+    /// It only measures: samples aren't modified, which is what
+    /// [`PitchShifter::set_output_limiter`] is for.
+    pub fn set_clip_ceiling(&mut self, ceiling: SampleReal) {
+        self.clip_ceiling = ceiling;
+    }
+
+    /// Whether any output sample of the last processing call
+    /// exceeded the clip ceiling, see
+    /// [`PitchShifter::last_output_clip_count`].
+    pub fn last_output_clipped(&self) -> bool {
+        self.clip_count > 0
+    }
+
+    /// How many output samples of the last processing call had an
+    /// absolute value above [`PitchShifter::set_clip_ceiling`],
+    /// after every gain stage and the limiter.
+    ///
+    /// It's counted while samples are written, and reset by each
+    /// call taking a buffer ([`PitchShifter::shift_pitch`] and its
+    /// variants, [`PitchShifter::push`], ...). With the adapters
+    /// ([`PitchShifter::shift_iter`], `shift_signal`), it's reset
+    /// when the adapter is created and covers every sample it has
+    /// yielded so far.
+    pub fn last_output_clip_count(&self) -> usize {
+        self.clip_count
+    }
+
+    /// When enabled, the overlap-add of resynthesized frames is
+    /// summed in `f64` rather than `f32`; input and output samples
+    /// stay `f32`.
+    ///
+    /// This is where rounding errors add up the most, so it makes
+    /// long renders a little more accurate, for `8 * frame_size`
+    /// extra bytes of memory. The switch can happen mid-stream
+    /// without discontinuity. Disabled by default, which keeps the
+    /// output identical to previous versions.
+    pub fn set_wide_accumulator(&mut self, wide: bool) {
+        match (wide, self.wide_accumulator.is_empty()) {
+            (true, true) => {
+                let acc = self.output_accumulator.iter();
+                self.wide_accumulator = acc.map(|s| *s as f64).collect();
+            },
+            (false, false) => {
+                let acc = self.output_accumulator.iter_mut();
+                acc.zip(&self.wide_accumulator).for_each(|(s, w)| *s = *w as SampleReal);
+                self.wide_accumulator = Vec::new();
+            },
+            _ => (),
+        }
+    }
+
+    /// When enabled, the output level is continuously adjusted to
+    /// match the input level, compensating for level changes due to
+    /// shifting (bins lost past Nyquist, collisions, windowing).
+    ///
+    /// The RMS level of both streams is followed with a 300 ms time
+    /// constant, the input being delayed by the shifter's latency so
+    /// that both describe the same audio, and the gain follows their
+    /// ratio with the same smoothing, which avoids pumping. It thus
+    /// takes about a second to settle. The gain is capped at +20 dB,
+    /// and held through silence. It's an RMS match, not a perceived
+    /// loudness (LUFS) match. It's applied before
+    /// [`PitchShifter::set_output_limiter`]. Disabled by default.
+    pub fn set_auto_gain_match(&mut self, enabled: bool) {
+        self.auto_gain = match enabled {
+            true => self.auto_gain.take().or_else(|| Some(AutoGain::new(self.sample_rate, self.frame_size))),
+            false => None,
+        };
+    }
+
+    /// Sets the smallest magnitude considered by analysis
+    /// features working in the log domain, so that silent bins
+    /// don't turn into `-inf` and propagate NaNs: the spectral
+    /// flatness (see [`PitchShifter::last_spectral_flatness`]), and
+    /// the frame level of [`PitchShifter::set_expander`], as an RMS
+    /// in sample scale, which the deepest expansion is measured
+    /// from on silence.
+    ///
+    /// It doesn't affect the resynthesized spectrum. The default
+    /// is `1e-9` (-180 dBFS), which is far below audible content at
+    /// any common sample scale; values of zero or less are raised
+    /// to the smallest positive one.
+    pub fn set_magnitude_floor(&mut self, floor: SampleReal) {
+        self.magnitude_floor = floor.max(SampleReal::MIN_POSITIVE);
+    }
+
+    /// See [`PitchShifter::set_magnitude_floor`]
+    pub fn magnitude_floor(&self) -> SampleReal {
+        self.magnitude_floor
+    }
+
+    /// Re-seeds the random generator shared by every stochastic
+    /// feature of this shifter.
+    ///
+    /// A fixed seed is used by default, so that output is
+    /// reproducible; pass some entropy here (time, OS randomness)
+    /// if you want every run to differ.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng = Rng::from_seed(seed);
+    }
+
+    /// Replaces the random generator, e.g. to share a sequence
+    /// across shifters. See [`PitchShifter::set_rng_seed`].
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    /// Current state of the random generator, which can be cloned
+    /// to replay a render exactly.
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// Delay between the input and output streams, in samples.
+    ///
+    /// Samples wait in the input FIFO for `frame_size - step`
+    /// samples before being analyzed, and each processed hop of
+    /// `step` samples is then read out over the next hop; with
+    /// `step = frame_size / over_sampling`, this amounts to
+    /// [`PitchShifter::frame_size`] samples.
+    pub fn latency_samples(&self, over_sampling: usize) -> usize {
+        let step = self.frame_size / over_sampling;
+        let fifo_latency = self.frame_size - step;
+        fifo_latency + step
+    }
+
+    /// Same as [`PitchShifter::latency_samples`], in milliseconds.
+    ///
+    /// This is the group delay resulting from the configured frame
+    /// size and hop, at [`PitchShifter::sample_rate`].
+    pub fn latency_ms(&self, over_sampling: usize) -> SampleReal {
+        let latency = self.latency_samples(over_sampling) as SampleReal;
+        1000.0 * latency / (self.sample_rate as SampleReal)
+    }
+
+    /// Whether calls of `block` samples with this `over_sampling`
+    /// keep the work of each call even, which real-time hosts can
+    /// check when loading settings.
+    ///
+    /// Output is correct and glitch-free with any block size: each
+    /// call returns as many samples as it's given, always delayed
+    /// by [`PitchShifter::latency_samples`], so buffering never
+    /// underruns. What varies is how many frames a call processes,
+    /// i.e. its cost. This returns `true` if and only if:
+    /// - `over_sampling` is in `1..=frame_size`, and
+    /// - `block` is at least one sample, and
+    /// - `block` is a multiple of the hop (`frame_size / over_sampling`),
+    ///   so that every call processes `block / hop` frames, or
+    ///   divides it, so that one call every `hop / block` processes
+    ///   a single frame and the others none.
+    ///
+    /// Otherwise, some calls process one more frame than others,
+    /// and the callback must then be able to afford
+    /// `block / hop + 1` (rounded down) frames. Checking the cost of
+    /// a frame against the time budget of a block is up to the
+    /// host, e.g. with [`PitchShifter::estimated_flops_per_second`].
+    pub fn supports_block_size(&self, block: usize, over_sampling: usize) -> bool {
+        if self.check_over_sampling(over_sampling).is_err() || block == 0 {
+            return false;
+        }
+
+        let step = self.frame_size / over_sampling;
+        block.is_multiple_of(step) || step.is_multiple_of(block)
+    }
+
+    /// Center frequency of bin `k`, in Hz
+    pub fn bin_frequency_hz(&self, k: usize) -> SampleReal {
+        (k * self.sample_rate) as SampleReal / (self.fft_size as SampleReal)
+    }
+
+    /// Bin closest to `hz`, which may be past the last bin
+    fn hz_to_bin(&self, hz: SampleReal) -> usize {
+        let bin = hz * (self.fft_size as SampleReal) / (self.sample_rate as SampleReal);
+        bin.max(0.0).round() as usize
+    }
+
+    /// Tames sibilance by attenuating the `band_hz` range (low, high)
+    /// of the shifted spectrum by up to `reduction_db` decibels.
+    ///
+    /// The attenuation is dynamic: it kicks in when the band holds
+    /// more than a quarter of the frame's energy and reaches
+    /// `reduction_db` when it holds half of it. Being relative to
+    /// the frame, this doesn't depend on the input level. A band
+    /// of `(5000.0, 10000.0)` suits most voices.
+    ///
+    /// A `reduction_db` of zero (or less) disables it, which is the
+    /// default.
+    pub fn set_deess(&mut self, band_hz: (SampleReal, SampleReal), reduction_db: SampleReal) {
+        self.deess = match reduction_db > 0.0 {
+            true => Some(DeEsser {
+                low_hz: band_hz.0,
+                high_hz: band_hz.1,
+                reduction_db,
+                gain: db_to_gain(-reduction_db),
+            }),
+            false => None,
+        };
+    }
+
+    fn apply_deess(&mut self) {
+        let Some(deess) = self.deess else {
+            return;
+        };
+
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let low = self.hz_to_bin(deess.low_hz).min(half_fft_size);
+        let high = self.hz_to_bin(deess.high_hz).clamp(low, half_fft_size);
+
+        let energy = |bins: &[SampleReal]| bins.iter().map(|m| m * m).sum::<SampleReal>();
+        let total = energy(&self.synthesized_magnitude[..half_fft_size]);
+        let band = &mut self.synthesized_magnitude[low..high];
+        if total <= 0.0 {
+            return;
+        }
+
+        let ratio = energy(band) / total;
+        let amount = ((ratio - DEESS_TRIGGER) / DEESS_TRIGGER).clamp(0.0, 1.0);
+        let gain = 1.0 + (deess.gain - 1.0) * amount;
+        band.iter_mut().for_each(|m| *m *= gain);
+    }
+
+    /// Tilts the shifted spectrum by `db_per_octave` around 1 kHz:
+    /// a positive value brightens the output, a negative one
+    /// darkens it, which helps restoring the tonal balance after
+    /// a large shift.
+    ///
+    /// This is a per-bin gain in the magnitude domain, applied
+    /// before resynthesis; the DC bin gets the gain of bin 1.
+    /// The default, `0.0`, is flat.
+    pub fn set_spectral_tilt(&mut self, db_per_octave: SampleReal) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        self.spectral_tilt = db_per_octave;
+        self.tilt_gains = match db_per_octave != 0.0 {
+            true => (0..half_fft_size).map(|k| {
+                let hz = self.bin_frequency_hz(k.max(1));
+                db_to_gain(db_per_octave * (hz / TILT_PIVOT_HZ).log2())
+            }).collect(),
+            false => Vec::new(),
+        };
+    }
+
+    fn apply_tilt(&mut self) {
+        let bins = self.synthesized_magnitude.iter_mut();
+        bins.zip(&self.tilt_gains).for_each(|(m, gain)| *m *= gain);
+    }
+
+    /// Smoothly turns down frames quieter than `threshold` dBFS
+    /// (downward expansion), e.g. to push noise down in speech
+    /// pauses: each dB below the threshold lowers the output by
+    /// `ratio - 1` more dB. Over a `knee` dB wide range centered on
+    /// the threshold, the ratio eases in gradually.
+    ///
+    /// The level is the RMS of each frame of input, and the gain
+    /// applies to the whole shifted spectrum. Unlike a hard gate,
+    /// which mutes whatever falls under its threshold, low level
+    /// content is only attenuated, more the quieter it is, so
+    /// breaths and tails fade instead of cutting off. From frame to
+    /// frame, the gain rises back within about 5 ms (attack) and
+    /// falls over about 100 ms (release), in steps of a hop.
+    ///
+    /// A `ratio` of `1.0` (or less) disables it, which is the
+    /// default; `2.0` to `4.0` suit speech. A `knee` of zero (or
+    /// less) is a hard knee.
+    pub fn set_expander(&mut self, threshold: SampleReal, ratio: SampleReal, knee: SampleReal) {
+        self.expander = match ratio > 1.0 {
+            true => Some(Expander {
+                threshold_db: threshold,
+                ratio,
+                knee_db: knee.max(0.0),
+                gain_db: 0.0,
+            }),
+            false => None,
+        };
+    }
+
+    fn apply_expander(&mut self, over_sampling: usize) {
+        let Some(expander) = &mut self.expander else {
+            return;
+        };
+
+        let power = self.in_fifo[..self.frame_size].iter().map(|s| s * s).sum::<SampleReal>();
+        let rms = (power / self.frame_size as SampleReal).sqrt();
+        let level_db = 20.0 * rms.max(self.magnitude_floor).log10();
+        let target_db = expander.target_db(level_db);
+
+        let hop_seconds = (self.frame_size / over_sampling) as SampleReal / self.sample_rate as SampleReal;
+        let time_constant = match target_db > expander.gain_db {
+            true => EXPANDER_ATTACK_SECONDS,
+            false => EXPANDER_RELEASE_SECONDS,
+        };
+        let coeff = (-hop_seconds / time_constant).exp();
+        expander.gain_db = coeff * expander.gain_db + (1.0 - coeff) * target_db;
+
+        let gain = db_to_gain(expander.gain_db);
+        self.synthesized_magnitude.iter_mut().for_each(|m| *m *= gain);
+    }
+
+    /// Whether the last processed frame was voiced, i.e. had a
+    /// strong harmonic structure, as opposed to noise-like content
+    /// (fricatives, breath, silence).
+    ///
+    /// Frames are classified from the spectral flatness of their
+    /// analyzed magnitudes (geometric mean over arithmetic mean,
+    /// with [`PitchShifter::magnitude_floor`] applied): a flatness
+    /// below `0.3` means voiced. A few sinusoids score close to
+    /// `0.0`, white noise around `0.85`.
+    pub fn last_frame_voiced(&self) -> bool {
+        self.voiced
+    }
+
+    fn classify_voicing(&mut self) {
+        let floor = self.magnitude_floor;
+        let magnitudes = self.fft_cplx.iter().map(|c| c.norm());
+        self.voiced = spectral_flatness(magnitudes, floor) < VOICED_FLATNESS;
+    }
+
+    /// Spectral flatness of the last processed frame, after shifting
+    /// and spectral effects: the geometric mean of its magnitudes
+    /// divided by their arithmetic mean.
+    ///
+    /// It's in `0.0..=1.0`: close to `0.0` for a few sinusoids, higher
+    /// for noise-like content, and `1.0` for silence. Magnitudes
+    /// are floored to [`PitchShifter::magnitude_floor`] to avoid
+    /// `ln(0)`.
+    pub fn last_spectral_flatness(&self) -> SampleReal {
+        self.flatness
+    }
+
+    fn measure_flatness(&mut self) {
+        let magnitudes = self.synthesized_magnitude.iter().copied();
+        self.flatness = spectral_flatness(magnitudes, self.magnitude_floor);
+    }
+
+    /// Spectral centroid of the last processed frame, in Hz: the mean
+    /// of bin frequencies, weighted by their magnitude.
+    ///
+    /// It's a simple brightness descriptor, measured on the analyzed
+    /// spectrum, i.e. before shifting and spectral effects. It's
+    /// `0.0` for silence.
+    pub fn last_spectral_centroid(&self) -> SampleReal {
+        self.centroid
+    }
+
+    /// Loudest bin of the last processed frame, measured on the
+    /// analyzed spectrum (before shifting); see
+    /// [`PitchShifter::bin_frequency_hz`] for its frequency.
+    pub fn last_peak_bin(&self) -> usize {
+        self.peak_bin
+    }
+
+    fn measure_analysis(&mut self) {
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        let mut loudest = 0.0;
+        self.peak_bin = 0;
+
+        for (k, bin) in self.fft_cplx.iter().enumerate() {
+            let magnitude = bin.norm();
+            weighted += magnitude * self.bin_frequency_hz(k);
+            total += magnitude;
+            if magnitude > loudest {
+                loudest = magnitude;
+                self.peak_bin = k;
+            }
+        }
+
+        self.centroid = match total > 0.0 {
+            true => weighted / total,
+            false => 0.0,
+        };
+    }
+
+    /// When enabled, the shifted spectrum of each frame is rescaled
+    /// so that its total energy matches the analyzed spectrum's.
+    ///
+    /// Without it, upward shifts lose the energy of bins moved past
+    /// Nyquist, and downward shifts make bins collide: colliding
+    /// magnitudes add up, which concentrates energy. This keeps the
+    /// loudness stable across shift amounts, but it's only an
+    /// approximation of perceived loudness. It's applied before
+    /// the other spectral effects (de-essing, tilt, ...), which can
+    /// still change the level. Disabled by default.
+    pub fn set_energy_preservation(&mut self, preserve: bool) {
+        self.energy_preservation = preserve;
+    }
+
+    fn apply_energy_preservation(&mut self) {
+        if !self.energy_preservation {
+            return;
+        }
+
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let magnitudes = &mut self.synthesized_magnitude[..half_fft_size];
+        let input: SampleReal = self.fft_cplx.iter().map(|c| c.norm_sqr()).sum();
+        let output: SampleReal = magnitudes.iter().map(|m| m * m).sum();
+        if output > 0.0 {
+            let gain = (input / output).sqrt();
+            magnitudes.iter_mut().for_each(|m| *m *= gain);
+        }
+    }
+
+    /// When enabled, each resynthesized frame is rescaled so that
+    /// its energy matches the analysis frame's, before overlap-add,
+    /// which steadies the level through frames where partials
+    /// collide, drop out past Nyquist or interfere (large shifts).
+    ///
+    /// Energies are compared in the time domain, through the
+    /// synthesis window: unlike [`PitchShifter::set_energy_preservation`],
+    /// this also catches frames whose energy is moved towards their
+    /// edges. The gain follows their ratio with a one-pole filter
+    /// (20 ms time constant), capped at +12 dB and held through
+    /// silence. Shifted frames still add up more or less coherently
+    /// in the overlap-add, so the output level only gets closer to
+    /// the input's: shifting a sine an octave up, from -3.5 dB to
+    /// -1.5 dB, and an octave down, from +0.4 dB to +1.3 dB.
+    ///
+    /// As it restores the level of the input, it undoes the level
+    /// changes of spectral effects applied earlier, like
+    /// [`PitchShifter::set_expander`] or
+    /// [`PitchShifter::set_spectral_tilt`]. Gains applied to the
+    /// whole output ([`SynthesisScaling`]'s `gain`,
+    /// [`PitchShifter::set_auto_gain_match`], the limiter) still
+    /// apply on top. Disabled by default.
+    pub fn set_frame_gain_normalization(&mut self, enabled: bool) {
+        self.frame_gain = match enabled {
+            true => self.frame_gain.or(Some(FrameGain { reference: 0.0, gain: 1.0 })),
+            false => None,
+        };
+    }
+
+    /// Smoothes the magnitude of each bin across consecutive frames
+    /// using a one-pole filter: `smoothed = coeff * smoothed +
+    /// (1 - coeff) * magnitude`, before resynthesis.
+    ///
+    /// This reduces the warbling of noisy or modulated input, at
+    /// the cost of blurring transients. `coeff` must be in `0.0..1.0`;
+    /// the closer to `1.0`, the smoother. The default, `0.0`,
+    /// disables it.
+    pub fn set_magnitude_smoothing(&mut self, coeff: SampleReal) {
+        self.magnitude_smoothing = coeff.clamp(0.0, 0.999);
+    }
+
+    fn apply_magnitude_smoothing(&mut self) {
+        let coeff = self.magnitude_smoothing;
+        if coeff == 0.0 {
+            return;
+        }
+
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let magnitudes = &mut self.synthesized_magnitude[..half_fft_size];
+        if !self.smoothing_primed {
+            self.smoothed_magnitude.copy_from_slice(magnitudes);
+            self.smoothing_primed = true;
+        }
+
+        for (m, smoothed) in magnitudes.iter_mut().zip(&mut self.smoothed_magnitude) {
+            *smoothed = coeff * *smoothed + (1.0 - coeff) * *m;
+            *m = *smoothed;
+        }
+    }
+
+    /// Adds harmonics of the spectral peaks of each frame to the
+    /// shifted spectrum, for some extra "presence".
+    ///
+    /// For each peak, harmonics 2 to 4 are added at `amount / n`
+    /// times its magnitude (`n` being the harmonic number), at
+    /// `n` times its frequency. Peaks are local maxima within
+    /// 40 dB of the loudest bin of the frame.
+    ///
+    /// Harmonics of high peaks can land above Nyquist: those are
+    /// simply dropped rather than folded back, but the ones just
+    /// below it can still sound harsh. An `amount` around `0.1`
+    /// is subtle; `0.0`, the default, disables it.
+    pub fn set_harmonic_excite(&mut self, amount: SampleReal) {
+        self.harmonic_excite = amount;
+    }
+
+    /// How coherently the phases of the last processed frame were
+    /// advanced, in `0.0..=1.0`, to quantify the vocoder's
+    /// phasiness.
+    ///
+    /// The shifted spectrum is split into regions around each of
+    /// its peaks (see [`PitchShifter::last_peaks`]), ending halfway
+    /// to the next one. A partial spreads over its whole region, so
+    /// ideally all of its bins advance their phase like the peak
+    /// does, keeping the shape of the partial; each bin's advance
+    /// over the hop rather drifts from the peak's by an angle,
+    /// and this is the length of the mean of these drifts as unit
+    /// vectors, weighted by magnitude.
+    ///
+    /// It's `1.0` when every region advances in lockstep, as with
+    /// [`PhaseMode::HarmonicLocked`] on voiced frames, or without
+    /// peaks. Steady partials stay close to it; transients, vibrato
+    /// and noise, where neighbouring bins measure different
+    /// frequencies, score lower: white noise is around `0.5`, and
+    /// bins drifting in every direction would give `0.0`. It's a
+    /// relative measure, for comparing settings on the same
+    /// material.
+    pub fn last_phase_coherence(&self) -> SampleReal {
+        self.phase_coherence
+    }
+
+    fn measure_phase_coherence(&mut self, over_sampling: usize) {
+        // phase advance over one hop, per Hz
+        let expected = (TAU / (over_sampling as SampleReal)) * self.padding_ratio();
+        let per_hz = expected / self.bin_frequency_hz(1);
+
+        let mut peaks = self.peaks.iter().peekable();
+        let Some(mut peak) = peaks.next() else {
+            self.phase_coherence = 1.0;
+            return;
+        };
+
+        let (mut re, mut im, mut total) = (0.0, 0.0, 0.0);
+        let bins = self.synthesized_frequency.iter().zip(&self.synthesized_magnitude);
+        for (k, (frequency, magnitude)) in bins.enumerate() {
+            while let Some(next) = peaks.next_if(|next| 2 * k >= peak.bin + next.bin) {
+                peak = next;
+            }
+
+            let drift = (frequency - self.synthesized_frequency[peak.bin]) * per_hz;
+            let (sin, cos) = drift.sin_cos();
+            re += magnitude * cos;
+            im += magnitude * sin;
+            total += magnitude;
+        }
+
+        self.phase_coherence = match total > 0.0 {
+            true => (re * re + im * im).sqrt() / total,
+            false => 1.0,
+        };
+    }
+
+    /// Spectral peaks of the last processed frame, from lowest to
+    /// highest frequency.
+    ///
+    /// They're detected in the shifted spectrum, after the de-esser
+    /// and tilt but before harmonic excitation, and recomputed at
+    /// each frame boundary. Peaks are local maxima within 40 dB of
+    /// the loudest bin of the frame.
+    pub fn last_peaks(&self) -> impl Iterator<Item = Peak> + '_ {
+        self.peaks.iter().copied()
+    }
+
+    /// Finds local maxima of `synthesized_magnitude`, within
+    /// [`PEAK_THRESHOLD`] of the loudest bin, and stores them in `peaks`
+    fn detect_peaks(&mut self) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let magnitudes = &self.synthesized_magnitude[..half_fft_size];
+        let loudest = magnitudes.iter().copied().fold(0.0, SampleReal::max);
+        let threshold = loudest * PEAK_THRESHOLD;
+
+        self.peaks.clear();
+        for k in 1..(half_fft_size - 1) {
+            let m = magnitudes[k];
+            if m > threshold && m > magnitudes[k - 1] && m >= magnitudes[k + 1] {
+                self.peaks.push(Peak {
+                    bin: k,
+                    magnitude: m,
+                    frequency: self.synthesized_frequency[k],
+                });
+            }
+        }
+    }
+
+    fn apply_harmonic_excite(&mut self) {
+        if self.harmonic_excite == 0.0 {
+            return;
+        }
+
+        let half_fft_size = (self.fft_size / 2) + 1;
+
+        for i in 0..self.peaks.len() {
+            let Peak { bin, magnitude, frequency } = self.peaks[i];
+
+            for n in 2..=EXCITED_HARMONICS {
+                let index = bin * n;
+                if index >= half_fft_size {
+                    break;
+                }
+
+                let added = magnitude * self.harmonic_excite / (n as SampleReal);
+                // don't take over the frequency of a louder partial
+                if self.synthesized_magnitude[index] < added {
+                    self.synthesized_frequency[index] = frequency * (n as SampleReal);
+                }
+                self.synthesized_magnitude[index] += added;
+            }
+        }
+    }
+
+    /// Synthesis bin that analysis bin `k` lands in when shifting
+    /// by `shift` semitones, or `None` if it falls past the last bin
+    /// (and is thus dropped).
+    ///
+    /// With the default [`BinMapping::Round`], this is
+    /// `round(k * 2^(shift / 12))`; with [`BinMapping::Interpolate`]
+    /// it's the lower of the two bins sharing the magnitude. Several
+    /// `k` landing in the same bin is what makes partials collide.
+    pub fn target_bin(&self, k: usize, shift: SampleReal) -> Option<usize> {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let shift = self.shift_ratio(shift);
+        let index = self.bin_mapping.index(k as SampleReal * shift);
+        (index < half_fft_size).then_some(index)
+    }
+
+    /// Selects how analysis bins are moved to synthesis bins,
+    /// see [`BinMapping`] for how each policy affects pitch accuracy
+    /// and artifacts.
+    pub fn set_bin_mapping(&mut self, mapping: BinMapping) {
+        self.bin_mapping = mapping;
+        self.update_fine_tune();
+    }
+
+    /// Selects how synthesized phases advance, see [`PhaseMode`].
+    ///
+    /// With [`PhaseMode::HarmonicLocked`], the fundamental of each
+    /// voiced frame (see [`PitchShifter::last_frame_voiced`]) is
+    /// estimated like in [`PitchShifter::retune_to_hz`], and every
+    /// bin is given the frequency of the shifted harmonic nearest to
+    /// it. Unvoiced frames, fundamentals below two bins, and the
+    /// band & linear shifts, which don't keep partials harmonic,
+    /// are processed as with [`PhaseMode::Standard`].
+    pub fn set_phase_mode(&mut self, mode: PhaseMode) {
+        self.phase_mode = mode;
+    }
+
+    /// Adds random jitter to the phase of each synthesized bin at
+    /// every frame, uniformly distributed within `±amount * π`.
+    ///
+    /// The jitter doesn't accumulate: each frame is offset from the
+    /// phases the vocoder would have used, which keeps partials in
+    /// tune. Small amounts (`0.05` to `0.2`) loosen the phase
+    /// relationships that make the vocoder ring metallically,
+    /// adding some air, with little effect on the level. Larger
+    /// ones whisperize: partials give way to noise shaped like the
+    /// spectrum, and at `1.0`, phases are entirely random, so that
+    /// frames overlap incoherently, about 8 dB quieter. The amount
+    /// is clamped to `0.0..=1.0`; `0.0`, the default, disables it.
+    /// Jitter comes from [`PitchShifter::rng`], so renders are
+    /// reproducible.
+    pub fn set_phase_dispersion(&mut self, amount: SampleReal) {
+        self.phase_dispersion = amount.clamp(0.0, 1.0);
+    }
+
+    /// Rough estimate of the floating point operations needed to
+    /// process one second of audio with this `over_sampling`.
+    ///
+    /// This is an estimate, not a guarantee: it's meant to compare
+    /// configurations and to guess how many shifters a machine
+    /// can run concurrently, not to replace benchmarking. It counts,
+    /// for each of the `sample_rate / step` frames per second:
+    /// - both real FFTs, at `5/2 * N * log2(N)` each,
+    /// - windowing and overlap-add, about `5 * N`,
+    /// - per-bin analysis and resynthesis, about `50` per bin,
+    ///   counting each transcendental function as `10`.
+    ///
+    /// Memory traffic, which often dominates on large frames,
+    /// isn't accounted for. Panics if `over_sampling` isn't in
+    /// `1..=frame_size`.
+    pub fn estimated_flops_per_second(&self, over_sampling: usize) -> u64 {
+        self.expect_over_sampling(over_sampling);
+        let step = (self.frame_size / over_sampling) as u64;
+        let frames_per_second = (self.sample_rate as u64).div_ceil(step);
+
+        let n = self.fft_size as f64;
+        let ffts = 2.0 * 2.5 * n * n.log2();
+        let windowing = 5.0 * (self.frame_size as f64);
+        let bins = 50.0 * ((self.fft_size / 2) + 1) as f64;
+        let per_frame = (ffts + windowing + bins) as u64;
+
+        per_frame * frames_per_second
+    }
+
+    /// Sets the window applied to frames before the forward FFT.
+    ///
+    /// Analysis and synthesis windows may differ to balance time
+    /// and frequency resolution, but they always span the whole
+    /// frame and share the same hop (`frame_size / over_sampling`).
+    /// A window which should be "shorter" must therefore be
+    /// expressed as one with zeroed ends.
+    ///
+    /// The output is rescaled from the overlap sum of the product
+    /// of both windows, so that any pair produces the same level as
+    /// the default Hann/Hann pair. Both windows default to
+    /// [`WindowFunction::Hann`].
+    pub fn set_analysis_window(&mut self, window: WindowFunction) {
+        self.analysis_window = window.coefficients(self.frame_size).into();
+        self.analysis_function = window;
+        self.update_window_compensation();
+        self.update_fine_tune();
+    }
+
+    /// Sets the window applied to frames after the inverse FFT,
+    /// before they're added to the output.
+    ///
+    /// See [`PitchShifter::set_analysis_window`] for constraints.
+    pub fn set_synthesis_window(&mut self, window: WindowFunction) {
+        self.synthesis_window = window.coefficients(self.frame_size).into();
+        self.synthesis_function = window;
+        self.update_window_compensation();
+        self.update_fine_tune();
+    }
+
+    fn update_window_compensation(&mut self) {
+        self.window_compensation = window::compensation(&self.analysis_window, &self.synthesis_window);
+    }
+
+    /// Checks whether the windows and hop satisfy the Constant
+    /// Overlap-Add condition for this `over_sampling`, i.e. whether
+    /// unshifted audio would come out without amplitude modulation.
+    ///
+    /// The overlap sum of the analysis/synthesis window product is
+    /// evaluated over one hop; this returns `true` if its ripple
+    /// (`(max - min) / mean`) is below 1%, about 0.09 dB.
+    ///
+    /// The default Hann windows, whose product is a squared Hann,
+    /// satisfy it for an `over_sampling` of 3 and more. If it returns
+    /// `false`, try a higher `over_sampling` or another window pair.
+    pub fn is_cola(&self, over_sampling: usize) -> bool {
+        let sum = self.overlap_sum(over_sampling);
+        let max = sum.iter().copied().fold(SampleReal::MIN, SampleReal::max);
+        let min = sum.iter().copied().fold(SampleReal::MAX, SampleReal::min);
+        let mean = sum.iter().sum::<SampleReal>() / (sum.len() as SampleReal);
+        mean > 0.0 && (max - min) / mean < COLA_TOLERANCE
+    }
+
+    /// Overlap sum of the window product at each position of a hop
+    fn overlap_sum(&self, over_sampling: usize) -> Vec<SampleReal> {
+        let step = (self.frame_size / over_sampling).max(1);
+        let mut sum = vec![0.0; step];
+        for k in 0..self.frame_size {
+            sum[k % step] += self.analysis_window[k] * self.synthesis_window[k];
+        }
+        sum
+    }
+
+    /// Factors scaling resynthesized frames with this `over_sampling`,
+    /// i.e. those set by [`PitchShifter::set_synthesis_scaling`], or
+    /// the default ones.
+    pub fn synthesis_scaling(&self, over_sampling: usize) -> SynthesisScaling {
+        if let Some(scaling) = self.scaling_override {
+            return scaling;
+        }
+
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let normalization = self.fft.normalization(self.fft_size);
+        SynthesisScaling {
+            transform: 1.0 / (half_fft_size as SampleReal * normalization.forward * normalization.inverse),
+            overlap: 1.0 / over_sampling as SampleReal,
+            window: self.window_compensation,
+            gain: 1.0,
+        }
+    }
+
+    /// Replaces the factors scaling resynthesized frames, e.g. to
+    /// adjust one of them for a custom [`FftBackend`] or to debug
+    /// level issues: start from [`PitchShifter::synthesis_scaling`]
+    /// and change what you need. `None` restores the default factors.
+    ///
+    /// Overridden factors are fixed: they no longer follow
+    /// `over_sampling`, windows or the backend's normalization.
+    pub fn set_synthesis_scaling(&mut self, scaling: Option<SynthesisScaling>) {
+        self.scaling_override = scaling;
+    }
+
+    /// Fraction of the FFT input covered by the frame
+    fn padding_ratio(&self) -> SampleReal {
+        (self.frame_size as SampleReal) / (self.fft_size as SampleReal)
+    }
+
+    /// Aligns the hop grid of the next stream to `samples`: frames
+    /// are processed once `samples` input samples have been fed
+    /// (modulo the hop, `frame_size / over_sampling`), then every hop.
+    /// The default, `0`, processes the first frame after one hop.
+    ///
+    /// For instance, a shifter fed from position `p` of a file,
+    /// with an offset of `step - p % step`, processes frames at the
+    /// same positions as one fed from the start of the file: once
+    /// both have a full frame of history, they analyze the exact same
+    /// frames. The offset applies when a stream starts, i.e. on a
+    /// new shifter or after [`PitchShifter::reset`]. It doesn't change
+    /// the latency: the output is still delayed by
+    /// [`PitchShifter::latency_samples`], but the silent ramp at the
+    /// beginning of the output, which depends on how many input
+    /// samples the first frames hold, moves with the grid.
+    pub fn set_start_offset(&mut self, samples: usize) {
+        self.start_offset = samples;
+    }
+
+    /// When enabled, the first frame of each stream doesn't measure
+    /// frequencies against the (arbitrary) zero phases left by
+    /// [`PitchShifter::new`] or [`PitchShifter::reset`]: every bin is
+    /// assumed to sit at its center frequency, and synthesis starts
+    /// from the analyzed phases.
+    ///
+    /// Otherwise, the frequencies measured on that frame are off by
+    /// up to `over_sampling / 2` bins, which features relying on
+    /// them ([`PitchShifter::last_peaks`], harmonic excitation, ...)
+    /// report or reproduce for one frame. Disabled by default, which
+    /// keeps the output identical to previous versions.
+    pub fn set_first_frame_phase_reset(&mut self, enabled: bool) {
+        self.first_frame_phase_reset = enabled;
+    }
+
+    /// When enabled, [`PitchShifter::process_clip`] shifts the
+    /// time-reversed clip, then reverses the result back, so that
+    /// it's still aligned with the input. Disabled by default.
+    ///
+    /// This is offline only: streaming methods like
+    /// [`PitchShifter::shift_pitch`] can't see the end of their
+    /// input, and ignore it. Pitch, length and level are the same,
+    /// and so is the time smearing of each frame, windows being
+    /// symmetric. What changes is the direction phases are carried
+    /// in from frame to frame, which mirrors the phasiness in time:
+    /// phases settle at the end of the clip instead of its
+    /// beginning, and each note inherits the phases of the one
+    /// after it rather than before it. It's a different flavor of
+    /// artifacts rather than fewer of them: attacks and decays
+    /// aren't measurably sharper either way.
+    pub fn set_reverse_processing(&mut self, enabled: bool) {
+        self.reverse_processing = enabled;
+    }
+
+    /// Phase of each bin of the last analyzed frame, see
+    /// [`PitchShifter::set_phase_state`]
+    pub fn last_phase(&self) -> &[SampleReal] {
+        &self.last_phase
+    }
+
+    /// Accumulated synthesis phase of each bin, see
+    /// [`PitchShifter::set_phase_state`]
+    pub fn phase_sum(&self) -> &[SampleReal] {
+        &self.phase_sum
+    }
+
+    /// Replaces the phase state carried from one frame to the next,
+    /// e.g. with [`PitchShifter::last_phase`] and
+    /// [`PitchShifter::phase_sum`] read from another shifter after
+    /// the previous chunk of a file split across workers, so that
+    /// the phases stay continuous across the seam.
+    ///
+    /// Both slices must hold `fft_size / 2 + 1` values (one per
+    /// bin, with [`PitchShifter::set_zero_padding`] included),
+    /// otherwise nothing is changed and
+    /// [`PitchShiftError::PhaseStateLength`] is returned. Phases
+    /// only line up if both shifters share their settings and
+    /// their frames fall on the same hop grid. The FIFOs aren't
+    /// part of this state: the worker resuming should first be fed
+    /// the last `frame_size` samples of the previous chunk (a
+    /// multiple of the hop), then receive the phase state, and its
+    /// output matches a single shifter after `frame_size` samples.
+    pub fn set_phase_state(&mut self, last_phase: &[SampleReal], phase_sum: &[SampleReal]) -> Result<(), PitchShiftError> {
+        let expected = self.last_phase.len();
+        if last_phase.len() != expected || phase_sum.len() != expected {
+            return Err(PitchShiftError::PhaseStateLength {
+                expected,
+                last_phase_len: last_phase.len(),
+                phase_sum_len: phase_sum.len(),
+            });
+        }
+
+        self.last_phase.copy_from_slice(last_phase);
+        self.phase_sum.copy_from_slice(phase_sum);
+        self.phases_primed = true;
+        Ok(())
+    }
+
+    /// Handle changing the shift and some spectral settings from
+    /// another thread (e.g. a UI) while this shifter runs on an
+    /// audio thread, without locks; see [`RealtimeParameters`].
+    ///
+    /// The first call allocates the handle, which starts with the
+    /// current settings; later calls return clones of it. Once it
+    /// exists, every change made through it overrides, at the next
+    /// frame boundary, all of the settings it covers, including
+    /// those set directly on the shifter in the meantime.
+    pub fn realtime_parameters(&mut self) -> RealtimeParameters {
+        if let Some((parameters, _)) = &self.realtime {
+            return parameters.clone();
+        }
+
+        let parameters = RealtimeParameters::new(realtime::Snapshot {
+            shift: self.shift,
+            harmonic_excite: self.harmonic_excite,
+            magnitude_smoothing: self.magnitude_smoothing,
+            limiter_ceiling: self.limiter_ceiling.unwrap_or(0.0),
+            energy_preservation: self.energy_preservation,
+            bypass_on_unity: self.bypass_on_unity,
+        });
+
+        self.realtime = Some((parameters.clone(), 0));
+        parameters
+    }
+
+    /// Applies changes made through `realtime_parameters`, if any
+    fn poll_realtime_parameters(&mut self) {
+        let Some((parameters, generation)) = &mut self.realtime else {
+            return;
+        };
+
+        if let Some(snapshot) = parameters.poll(generation) {
+            // not `set_shift`, which can measure a fine tune correction
+            self.shift = snapshot.shift;
+            self.set_harmonic_excite(snapshot.harmonic_excite);
+            self.set_magnitude_smoothing(snapshot.magnitude_smoothing);
+            self.set_output_limiter(snapshot.limiter_ceiling);
+            self.set_energy_preservation(snapshot.energy_preservation);
+            self.set_bypass_on_unity(snapshot.bypass_on_unity);
+        }
+    }
+
+    /// Smoothes the jump at the loop point of looped playback: call
+    /// this between the last sample of an iteration of the loop and
+    /// the first one of the next.
+    ///
+    /// Unless the loop is seamless, the input jumps at that point,
+    /// and the frames spanning it turn the jump into a click, while
+    /// the phase jump confuses frequency measurement. This fades the
+    /// last 5 ms of input before the loop point out and the first
+    /// 5 ms after it in (which leaves a short dip instead of a click),
+    /// and measures the frequencies of the next frame as if every bin
+    /// sat at its center frequency. Synthesis phases carry on
+    /// unchanged, so the output stays continuous.
+    ///
+    /// This is unnecessary for seamless loops, which the shifter
+    /// handles like any continuous input.
+    pub fn loop_boundary(&mut self) {
+        // `in_fifo[..overlap]` holds the latest input, in order
+        let written = self.overlap;
+        let len = (LOOP_FADE_SECONDS * self.sample_rate as SampleReal) as usize;
+        let fade_out = len.min(written);
+        for (j, sample) in self.in_fifo[(written - fade_out)..written].iter_mut().enumerate() {
+            *sample *= 1.0 - loop_fade_gain(j, fade_out);
+        }
+
+        self.loop_fade = (0, len);
+        self.loop_phase_reset = true;
+    }
+
+    /// Applies the fade-in following `loop_boundary` to `range` of
+    /// `in_fifo`, which was just written
+    fn fade_in_loop(&mut self, range: std::ops::Range<usize>) {
+        let (done, len) = self.loop_fade;
+        if done >= len {
+            return;
+        }
+
+        let count = range.len();
+        for (j, sample) in self.in_fifo[range].iter_mut().enumerate() {
+            if done + j < len {
+                *sample *= loop_fade_gain(done + j, len);
+            }
+        }
+
+        self.loop_fade.0 = (done + count).min(len);
+    }
+
+    /// Starts writing the spectra of every processed frame to a
+    /// CSV file at `path` (requires the `debug_dump` feature), e.g.
+    /// to attach them to a bug report. A dump already in progress
+    /// is stopped first.
+    ///
+    /// The file starts with a `frame,stage,bin,magnitude,phase`
+    /// header, followed by one line per bin, per stage, per frame:
+    /// - `frame` counts the frames processed by this shifter since
+    ///   its creation, starting at `1`
+    /// - `stage` is `analysis` for the spectrum of the windowed
+    ///   input, or `synthesis` for the one fed to the inverse FFT
+    /// - `bin` goes from `0` to `fft_size / 2`
+    /// - `magnitude` and `phase` (in radians) are in the scale of
+    ///   the FFT backend
+    ///
+    /// Frames passed through by [`PitchShifter::set_bypass_on_unity`]
+    /// aren't transformed, so they aren't written.
+    #[cfg(feature = "debug_dump")]
+    pub fn start_spectral_dump<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let dump = dump::SpectralDump::create(path.as_ref())?;
+        let previous = self.spectral_dump.replace(dump);
+        previous.map_or(Ok(()), dump::SpectralDump::finish)
+    }
+
+    /// Stops the dump started by [`PitchShifter::start_spectral_dump`],
+    /// flushing the file; returns the first error met while writing
+    /// it, if any. Does nothing if no dump is in progress.
+    #[cfg(feature = "debug_dump")]
+    pub fn stop_spectral_dump(&mut self) -> std::io::Result<()> {
+        self.spectral_dump.take().map_or(Ok(()), dump::SpectralDump::finish)
+    }
+
+    /// Forgets everything about the audio processed so far, as if
+    /// the shifter was new; settings are kept.
+    ///
+    /// Call this between unrelated streams or clips, so that the
+    /// end of one doesn't bleed into the beginning of the next.
+    pub fn reset(&mut self) {
+        self.in_fifo.fill(0.0);
+        self.out_fifo.fill(0.0);
+        self.last_phase.fill(0.0);
+        self.phase_sum.fill(0.0);
+        self.output_accumulator.fill(0.0);
+        self.wide_accumulator.fill(0.0);
+        self.overlap = 0;
+        self.started = false;
+        self.pending.clear();
+        self.carry.clear();
+        self.dry_delay.clear();
+        self.stretch_input.clear();
+        self.stretch_outputs = 0;
+        self.stretch_consumed = 0;
+        self.loop_fade = (0, 0);
+        self.loop_phase_reset = false;
+        self.envelope = 0.0;
+        if let Some(expander) = &mut self.expander {
+            expander.gain_db = 0.0;
+        }
+        if let Some(frame_gain) = &mut self.frame_gain {
+            frame_gain.gain = 1.0;
+        }
+        if let Some(highpass) = &mut self.highpass {
+            *highpass = HighPass::new(highpass.cutoff_hz, self.sample_rate);
+        }
+        if let Some(emphasis) = &mut self.emphasis {
+            emphasis.previous_input = 0.0;
+        }
+        self.smoothing_primed = false;
+        self.phases_primed = false;
+        self.bypassed = false;
+        if let Some(auto_gain) = &mut self.auto_gain {
+            auto_gain.reset(self.frame_size);
+        }
+    }
+
+    /// Input FIFO latency of the hop in progress, after placing
+    /// `overlap` at the beginning of a stream with `over_sampling`.
+    ///
+    /// A new `over_sampling` only applies from the next frame on:
+    /// the hop in progress was started with the previous one, and
+    /// `out_fifo` holds exactly that many output samples. With
+    /// `step = frame_size / hop_over_sampling` in `1..=frame_size`,
+    /// `overlap` is in `fifo_latency..frame_size`: writes to
+    /// `in_fifo` stay below `frame_size`, and reads from `out_fifo`,
+    /// at `overlap - fifo_latency`, below `step`, the part of it
+    /// which the last frame filled. `process_frame` then reads
+    /// `next_step..frame_size` of `in_fifo` and
+    /// `next_step..(next_step + frame_size)` of the accumulator,
+    /// which is `2 * frame_size` long.
+    fn prepare_fifos(&mut self, over_sampling: usize) -> usize {
+        self.expect_over_sampling(over_sampling);
+
+        // `overlap` can't mark the beginning of a stream, since it's
+        // zero all the time when `over_sampling` is 1
+        if !self.started {
+            let step = self.frame_size / over_sampling;
+            self.started = true;
+            self.hop_over_sampling = over_sampling;
+            self.overlap = self.frame_size - step + (step - self.start_offset % step) % step;
+        }
+
+        self.frame_size - self.frame_size / self.hop_over_sampling
+    }
+
+    /// Processes the frame completing the hop in progress, then
+    /// starts one for `over_sampling`; returns its FIFO latency
+    fn complete_hop(&mut self, over_sampling: usize, shift: SampleReal) -> usize {
+        self.process_frame(self.hop_over_sampling, over_sampling, shift);
+        self.hop_over_sampling = over_sampling;
+        self.overlap = self.frame_size - self.frame_size / over_sampling;
+        self.overlap
+    }
+
+    fn process_sample(&mut self, over_sampling: usize, shift: SampleReal, sample: SampleReal) -> SampleReal {
+        let fifo_latency = self.prepare_fifos(over_sampling);
+
+        self.last_over_sampling = over_sampling;
+        self.last_shift = shift;
+
+        let filtered = match &mut self.highpass {
+            Some(highpass) => highpass.process(sample),
+            None => sample,
+        };
+        self.in_fifo[self.overlap] = match &mut self.emphasis {
+            Some(emphasis) => emphasis.process(filtered),
+            None => filtered,
+        };
+        self.fade_in_loop(self.overlap..(self.overlap + 1));
+        let output = self.out_fifo[self.overlap - fifo_latency];
+        let output = self.finish_output(sample, output);
+
+        self.overlap += 1;
+        if self.overlap >= self.frame_size {
+            self.complete_hop(over_sampling, shift);
+        }
+
+        output
+    }
+
+    /// Same as calling `process_sample` on each sample of `buffer`,
+    /// replacing it with the output, with whole runs of samples
+    /// between hop boundaries copied at once
+    fn process_block(&mut self, over_sampling: usize, shift: SampleReal, buffer: &mut [SampleReal]) {
+        let mut fifo_latency = self.prepare_fifos(over_sampling);
+        self.clip_count = 0;
+
+        self.last_over_sampling = over_sampling;
+        self.last_shift = shift;
+
+        let mut i = 0;
+        while i < buffer.len() {
+            let run = (self.frame_size - self.overlap).min(buffer.len() - i);
+            let read = self.overlap - fifo_latency;
+
+            let queued = &mut self.in_fifo[self.overlap..][..run];
+            queued.copy_from_slice(&buffer[i..][..run]);
+            if let Some(highpass) = &mut self.highpass {
+                queued.iter_mut().for_each(|s| *s = highpass.process(*s));
+            }
+            if let Some(emphasis) = &mut self.emphasis {
+                queued.iter_mut().for_each(|s| *s = emphasis.process(*s));
+            }
+            self.fade_in_loop(self.overlap..(self.overlap + run));
+
+            for (sample, output) in buffer[i..][..run].iter_mut().zip(read..) {
+                *sample = self.finish_output(*sample, self.out_fifo[output]);
+            }
+
+            i += run;
+            self.overlap += run;
+            if self.overlap >= self.frame_size {
+                fifo_latency = self.complete_hop(over_sampling, shift);
+            }
+        }
+    }
+
+    /// Post-processing of each output sample
+    fn finish_output(&mut self, input: SampleReal, mut output: SampleReal) -> SampleReal {
+        if let Some(carried) = self.carry.pop_front() {
+            output += carried;
+        }
+
+        // the latency is the frame size, whatever `over_sampling`
+        if let Some(auto_gain) = &mut self.auto_gain {
+            output = auto_gain.process(self.frame_size, input, output);
+        }
+
+        if let Some(ceiling) = self.limiter_ceiling {
+            output = soft_limit(output, ceiling);
+        }
+
+        if output.abs() > self.clip_ceiling {
+            self.clip_count += 1;
+        }
+
+        output
+    }
+
+    /// Processes the frame in `in_fifo`, the previous one being
+    /// `frame_size / over_sampling` samples earlier, and moves the
+    /// FIFOs to the next, `frame_size / next_over_sampling` later
+    fn process_frame(&mut self, over_sampling: usize, next_over_sampling: usize, shift: SampleReal) {
+        self.frames += 1;
+        self.poll_realtime_parameters();
+
+        let step = self.frame_size / over_sampling;
+        let next_step = self.frame_size / next_over_sampling;
+        let shift = match self.envelope_mod {
+            Some((base_shift, env_depth)) => {
+                let envelope = self.follow_envelope(step);
+                self.shift_ratio(base_shift + env_depth * envelope)
+            },
+            None => shift,
+        };
+
+        if self.bypass_on_unity && shift == 1.0 && !self.spectral_effects() {
+            self.bypass_frame(over_sampling, next_step);
+            self.in_fifo.copy_within(next_step..self.frame_size, 0);
+            return;
+        }
+
+        // frames are centered in the padded FFT input (if any),
+        // so that resynthesized grains come out centered too
+        let pad = (self.fft_size - self.frame_size) / 2;
+        self.fft_real.fill(0.0);
+        for k in 0..self.frame_size {
+            self.fft_real[pad + k] = self.in_fifo[k] * self.analysis_window[k];
+        }
+
+        if let Some(frame_gain) = &mut self.frame_gain {
+            let frame = self.fft_real[pad..].iter().zip(self.synthesis_window.iter());
+            frame_gain.reference = frame.map(|(x, w)| (x * w) * (x * w)).sum();
+        }
+
+        self.restore_scratch();
+        self.fft.forward(&mut self.fft_real, &mut self.fft_cplx, &mut self.fft_scratch);
+        self.deemphasize();
+
+        #[cfg(feature = "debug_dump")]
+        if let Some(dump) = &mut self.spectral_dump {
+            dump.write(self.frames, "analysis", &self.fft_cplx);
+        }
+
+        let shift = match self.retune_hz {
+            Some(target_hz) => self.retune_ratio(over_sampling, target_hz),
+            None => shift,
+        };
+
+        let shift = match self.fine_tune {
+            true => self.fine_tune_ratio(over_sampling, shift),
+            false => shift,
+        };
+
+        self.process_spectrum(over_sampling, shift);
+
+        #[cfg(feature = "debug_dump")]
+        if let Some(dump) = &mut self.spectral_dump {
+            dump.write(self.frames, "synthesis", &self.fft_cplx);
+        }
+
+        self.overlap_add(over_sampling, next_step);
+        self.in_fifo.copy_within(next_step..self.frame_size, 0);
+    }
+
+    /// Whether any setting alters the spectrum of unshifted frames,
+    /// or their shift once analyzed, which then can't be bypassed
+    fn spectral_effects(&self) -> bool {
+        self.retune_hz.is_some()
+            || self.fine_tune
+            || self.bin_offset.is_some()
+            || self.band_hz.is_some()
+            || self.spectrogram.is_some()
+            || self.emphasis.is_some()
+            || self.deess.is_some()
+            || self.expander.is_some()
+            || self.frame_gain.is_some()
+            || self.spectral_tilt != 0.0
+            || self.harmonic_excite > 0.0
+            || self.magnitude_smoothing > 0.0
+            || self.energy_preservation
+            || self.phase_mode != PhaseMode::Standard
+            || self.phase_dispersion > 0.0
+    }
+
+    /// Overlap-adds the windowed frame in `in_fifo` as is, instead
+    /// of its resynthesis: at unity, that's what the vocoder gives,
+    /// without the FFTs. Frames overlap as usual, so going in and
+    /// out of bypass crossfades over a frame; since phases aren't
+    /// tracked meanwhile, the next analyzed frame starts from its
+    /// own phases, which keeps it aligned with the bypassed ones.
+    fn bypass_frame(&mut self, over_sampling: usize, step: usize) {
+        let pad = (self.fft_size - self.frame_size) / 2;
+        // the gain of the FFT round trip, which resynthesis undoes
+        let normalization = self.fft.normalization(self.fft_size);
+        let round_trip = self.fft_size as SampleReal * normalization.forward * normalization.inverse;
+
+        self.fft_real.fill(0.0);
+        for k in 0..self.frame_size {
+            self.fft_real[pad + k] = self.in_fifo[k] * self.analysis_window[k] * round_trip;
+        }
+
+        self.accumulate_frame(over_sampling, step);
+        self.bypassed = true;
+    }
+
+    /// Undoes the pre-emphasis of the analyzed spectrum, leaving
+    /// the phases (and frequency estimates) as measured on it
+    fn deemphasize(&mut self) {
+        let Some(emphasis) = &self.emphasis else {
+            return;
+        };
+
+        let emphasized: SampleReal = self.fft_cplx.iter().map(|c| c.norm_sqr()).sum();
+        let bins = self.fft_cplx.iter_mut();
+        bins.zip(&emphasis.gains).for_each(|(c, gain)| *c *= gain);
+
+        // the reference was measured on the emphasized frame
+        if let Some(frame_gain) = &mut self.frame_gain {
+            if emphasized > 0.0 {
+                let restored: SampleReal = self.fft_cplx.iter().map(|c| c.norm_sqr()).sum();
+                frame_gain.reference *= restored / emphasized;
+            }
+        }
+    }
+
+    /// Reallocates the scratch space freed by
+    /// [`PitchShifter::process_with_scratch`], if needed
+    fn restore_scratch(&mut self) {
+        if self.fft_scratch.len() < self.scratch_len {
+            self.fft_scratch.resize(self.scratch_len, COMPLEX_ZERO);
+        }
+    }
+
+    /// Inverse FFT of `fft_cplx`, overlap-added to the output, see
+    /// `accumulate_frame`
+    fn overlap_add(&mut self, over_sampling: usize, step: usize) {
+        self.restore_scratch();
+        self.fft.inverse(&mut self.fft_cplx, &mut self.fft_real, &mut self.fft_scratch);
+        self.accumulate_frame(over_sampling, step);
+    }
+
+    /// Scaling of frames overlap-added with this `over_sampling`,
+    /// and what it's divided by for each sample: this is
+    /// `synthesis_scaling(over_sampling).total()`, in the historical
+    /// order of operations, which keeps output identical
+    fn synthesis_factor(&self, over_sampling: usize) -> (SampleReal, SampleReal) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let normalization = self.fft.normalization(self.fft_size);
+        let acc_oversamp: SampleReal = 2.0 / (half_fft_size * over_sampling) as SampleReal;
+        let acc_oversamp = acc_oversamp * self.window_compensation;
+        let acc_oversamp = acc_oversamp / (normalization.forward * normalization.inverse);
+        match self.scaling_override {
+            Some(scaling) => (scaling.total(), 1.0),
+            None => (acc_oversamp, 2.0),
+        }
+    }
+
+    /// Frame in `fft_real`, scaled for `over_sampling` and added to
+    /// the output accumulator; the first `step` samples, now
+    /// complete, are moved to `out_fifo`.
+    ///
+    /// Frames are weighted by their hop, so that the overlap-add
+    /// stays level when `step` differs from `frame_size / over_sampling`,
+    /// i.e. when `over_sampling` changes: each frame then counts for
+    /// the mean of the hops before and after it.
+    fn accumulate_frame(&mut self, over_sampling: usize, step: usize) {
+        let pad = (self.fft_size - self.frame_size) / 2;
+        let (acc_oversamp, halving) = self.synthesis_factor(over_sampling);
+        let previous_step = self.frame_size / over_sampling;
+        let acc_oversamp = match step == previous_step {
+            true => acc_oversamp,
+            false => acc_oversamp * (previous_step + step) as SampleReal / (2 * previous_step) as SampleReal,
+        };
+
+        let acc_oversamp = match self.frame_gain.is_some() {
+            true => acc_oversamp * self.update_frame_gain(step),
+            false => acc_oversamp,
+        };
+
+        let products = (0..self.frame_size).map(|k| {
+            self.synthesis_window[k] * self.fft_real[pad + k] * acc_oversamp / halving
+        });
+
+        if self.wide_accumulator.is_empty() {
+            self.output_accumulator.iter_mut().zip(products).for_each(|(acc, p)| *acc += p);
+            self.out_fifo[..step].copy_from_slice(&self.output_accumulator[..step]);
+        } else {
+            self.wide_accumulator.iter_mut().zip(products).for_each(|(acc, p)| *acc += p as f64);
+            let out = self.out_fifo[..step].iter_mut();
+            out.zip(&self.wide_accumulator).for_each(|(s, acc)| *s = *acc as SampleReal);
+        }
+
+        self.shift_accumulator(step);
+    }
+
+    /// Smoothed gain bringing the inverse transform in `fft_real`
+    /// to the energy of its analysis frame, `step` samples after
+    /// the previous one
+    fn update_frame_gain(&mut self, step: usize) -> SampleReal {
+        let pad = (self.fft_size - self.frame_size) / 2;
+        let normalization = self.fft.normalization(self.fft_size);
+        let round_trip = self.fft_size as SampleReal * normalization.forward * normalization.inverse;
+
+        let frame = self.fft_real[pad..].iter().zip(self.synthesis_window.iter());
+        let output: SampleReal = frame.map(|(x, w)| (x * w) * (x * w)).sum();
+        let output = output / (round_trip * round_trip);
+
+        let Some(frame_gain) = &mut self.frame_gain else {
+            return 1.0;
+        };
+
+        // silence, on either side, keeps the previous gain
+        let silence = SILENCE_POWER * self.frame_size as SampleReal;
+        let target = match frame_gain.reference > silence && output > silence {
+            true => (frame_gain.reference / output).sqrt().min(FRAME_GAIN_MAX),
+            false => frame_gain.gain,
+        };
+
+        let time_constant = FRAME_GAIN_SECONDS * self.sample_rate as SampleReal;
+        let coeff = (-(step as SampleReal) / time_constant).exp();
+        frame_gain.gain = coeff * frame_gain.gain + (1.0 - coeff) * target;
+        frame_gain.gain
+    }
+
+    /// Discards the first `step` samples of the output accumulator
+    fn shift_accumulator(&mut self, step: usize) {
+        let range = step..(step + self.frame_size);
+        self.output_accumulator.copy_within(range.clone(), 0);
+        if !self.wide_accumulator.is_empty() {
+            self.wide_accumulator.copy_within(range, 0);
+        }
+    }
+
+    /// Smoothed RMS of the frame in `in_fifo`, one hop after the
+    /// previous call
+    fn follow_envelope(&mut self, step: usize) -> SampleReal {
+        let power = self.in_fifo[..self.frame_size].iter().map(|s| s * s).sum::<SampleReal>();
+        let rms = (power / self.frame_size as SampleReal).sqrt();
+
+        let hop_seconds = step as SampleReal / self.sample_rate as SampleReal;
+        let coeff = (-hop_seconds / ENVELOPE_SECONDS).exp();
+        self.envelope = coeff * self.envelope + (1.0 - coeff) * rms;
+        self.envelope
+    }
+
+    /// Ratio moving the fundamental of the frame in `fft_cplx` to
+    /// `target_hz`, or `1.0` if it's unvoiced
+    fn retune_ratio(&mut self, over_sampling: usize, target_hz: SampleReal) -> SampleReal {
+        self.classify_voicing();
+        match self.voiced {
+            true => self.estimate_fundamental(over_sampling).map_or(1.0, |hz| target_hz / hz),
+            false => 1.0,
+        }
+    }
+
+    /// True frequency of the lowest peak of `fft_cplx` within
+    /// [`FUNDAMENTAL_THRESHOLD`] of the loudest bin, from its phase
+    /// advance since the previous frame
+    fn estimate_fundamental(&self, over_sampling: usize) -> Option<SampleReal> {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let magnitude = |k: usize| self.fft_cplx[k].norm();
+        let loudest = (0..half_fft_size).map(magnitude).fold(0.0, SampleReal::max);
+        let threshold = loudest * FUNDAMENTAL_THRESHOLD;
+
+        let k = (1..(half_fft_size - 1)).find(|&k| {
+            let m = magnitude(k);
+            m > threshold && m > magnitude(k - 1) && m >= magnitude(k + 1)
+        })?;
+
+        let k_real = k as SampleReal;
+        let expected = (TAU / (over_sampling as SampleReal)) * self.padding_ratio();
+        let delta_phase = (self.fft_cplx[k].arg() - self.last_phase[k]) - k_real * expected;
+        let delta_phase = (delta_phase + PI).rem_euclid(TAU) - PI;
+        let deviation = delta_phase * (over_sampling as SampleReal) / TAU / self.padding_ratio();
+
+        let hz = (k_real + deviation) * self.bin_frequency_hz(1);
+        (hz > 0.0).then_some(hz)
+    }
+
+    /// Shifts the spectrum in `fft_cplx`, in place
+    fn process_spectrum(&mut self, over_sampling: usize, shift: SampleReal) {
+        if let Some(magnitudes) = &mut self.spectrogram {
+            magnitudes.clear();
+            magnitudes.extend(self.fft_cplx.iter().map(|c| c.norm()));
+        }
+        self.classify_voicing();
+        // before `analyze` replaces `last_phase`
+        let harmonic = self.phase_mode == PhaseMode::HarmonicLocked && self.band_hz.is_none() && self.bin_offset.is_none();
+        let fundamental = match harmonic && self.voiced {
+            true => self.estimate_fundamental(over_sampling),
+            false => None,
+        };
+        self.measure_analysis();
+        self.analyze(over_sampling, shift);
+        self.apply_energy_preservation();
+        self.apply_magnitude_smoothing();
+        self.apply_deess();
+        self.apply_tilt();
+        self.detect_peaks();
+        self.apply_harmonic_excite();
+        self.measure_flatness();
+        self.apply_expander(over_sampling);
+        if let Some(hz) = fundamental {
+            self.lock_harmonics(hz * shift);
+        }
+        self.measure_phase_coherence(over_sampling);
+        self.resynthesize(over_sampling);
+    }
+
+    /// Moves the frequency of each synthesized bin to the nearest
+    /// harmonic of `fundamental_hz`, see [`PhaseMode::HarmonicLocked`]
+    fn lock_harmonics(&mut self, fundamental_hz: SampleReal) {
+        let bin_hz = self.bin_frequency_hz(1);
+        // closer harmonics can't be told apart
+        if fundamental_hz < 2.0 * bin_hz {
+            return;
+        }
+
+        let bins = self.synthesized_frequency.iter_mut().zip(&self.synthesized_magnitude);
+        for (k, (frequency, magnitude)) in bins.enumerate() {
+            let harmonic = (k as SampleReal * bin_hz / fundamental_hz).round();
+            if *magnitude > 0.0 && harmonic >= 1.0 {
+                *frequency = harmonic * fundamental_hz;
+            }
+        }
+    }
+
+    /// Measures the true frequency of each bin of `fft_cplx` and
+    /// moves it to its shifted position in `synthesized_*`
+    fn analyze(&mut self, over_sampling: usize, shift: SampleReal) {
+        let fs_real = self.fft_size as SampleReal;
+        let half_fft_size = (self.fft_size / 2) + 1;
+
+        let bin_frequencies = self.sample_rate as SampleReal / fs_real;
+        // expected phase advance of bin 1 over one hop
+        let expected = (TAU / (over_sampling as SampleReal)) * self.padding_ratio();
+
+        let pitch_weight = shift * bin_frequencies;
+        let oversamp_weight = ((over_sampling as SampleReal) / TAU / self.padding_ratio()) * pitch_weight;
+
+        self.synthesized_magnitude.fill(0.0);
+        self.synthesized_frequency.fill(0.0);
+
+        // pretend the previous frame matched bin centers exactly;
+        // after a bypass, synthesis resumes from the analyzed phases
+        let first_frame = (self.first_frame_phase_reset && !self.phases_primed) || self.bypassed;
+        if first_frame || self.loop_phase_reset {
+            for k in 0..half_fft_size {
+                self.last_phase[k] = self.fft_cplx[k].arg() - (k as SampleReal) * expected;
+            }
+        }
+        self.phases_primed = true;
+        self.bypassed = false;
+        self.loop_phase_reset = false;
+
+        let band = self.band_hz.map(|(low_hz, high_hz)| {
+            (low_hz / bin_frequencies, high_hz / bin_frequencies)
+        });
+        let offset = self.bin_offset.unwrap_or(0.0);
+
+        for k in 0..half_fft_size {
+            let k_real = k as SampleReal;
+            // proportion of the bin which is shifted
+            let weight = band.map_or(1.0, |band| band_weight(k_real, band));
+            let target = k_real * shift + offset;
+            let index = self.bin_mapping.index(target);
+            let in_range = target >= 0.0 && index < half_fft_size;
+            if in_range || weight < 1.0 {
+                let (magnitude, phase) = self.fft_cplx[k].to_polar();
+                let mut delta_phase = (phase - self.last_phase[k]) - k_real * expected;
+                // must not round here for some reason
+                let mut qpd = (delta_phase / PI) as i64;
+
+                if qpd >= 0 {
+                    qpd += qpd & 1;
+                } else {
+                    qpd -= qpd & 1;
+                }
+
+                delta_phase -= PI * qpd as SampleReal;
+                self.last_phase[k] = phase;
+                let frequency = k_real * pitch_weight + oversamp_weight * delta_phase + offset * bin_frequencies;
+
+                // start synthesis from the analyzed phases, which
+                // `resynthesize` then advances by `frequency`
+                let seed = |frequency| first_frame.then(|| phase - expected * frequency / bin_frequencies);
+
+                if weight > 0.0 && in_range {
+                    self.place_bin(target, magnitude * weight, frequency, seed(frequency));
+                }
+
+                if weight < 1.0 {
+                    let frequency = (frequency - offset * bin_frequencies) / shift;
+                    self.place_bin(k_real, magnitude * (1.0 - weight), frequency, seed(frequency));
+                }
+            }
+        }
+    }
+
+    /// Adds a partial of `magnitude` & `frequency` (in Hz) to the
+    /// synthesized bin(s) at `target`, seeding their phase if
+    /// `seed` is given
+    fn place_bin(&mut self, target: SampleReal, magnitude: SampleReal, frequency: SampleReal, seed: Option<SampleReal>) {
+        let half_fft_size = (self.fft_size / 2) + 1;
+        let index = self.bin_mapping.index(target);
+        let interpolate = self.bin_mapping == BinMapping::Interpolate;
+
+        if let Some(seed) = seed {
+            self.phase_sum[index] = seed;
+            if interpolate && index + 1 < half_fft_size {
+                self.phase_sum[index + 1] = seed;
+            }
+        }
+
+        if interpolate {
+            let fraction = target - index as SampleReal;
+            self.add_to_bin(index, magnitude * (1.0 - fraction), frequency);
+            if index + 1 < half_fft_size {
+                self.add_to_bin(index + 1, magnitude * fraction, frequency);
+            }
+        } else {
+            self.add_to_bin(index, magnitude, frequency);
+        }
+    }
+
+    fn add_to_bin(&mut self, index: usize, magnitude: SampleReal, frequency: SampleReal) {
+        // partials colliding in a bin historically leave it the
+        // frequency of the last one; with a band, every bin gets an
+        // unshifted partial too, which must not override a louder
+        // shifted one landing on it (or vice versa)
+        if self.band_hz.is_none() || magnitude >= self.synthesized_magnitude[index] {
+            self.synthesized_frequency[index] = frequency;
+        }
+
+        self.synthesized_magnitude[index] += magnitude;
+    }
+
+    /// Accumulates the phase of each synthesized bin and writes
+    /// the resulting spectrum to `fft_cplx`
+    fn resynthesize(&mut self, over_sampling: usize) {
+        let fs_real = self.fft_size as SampleReal;
+        let half_fft_size = (self.fft_size / 2) + 1;
+
+        let bin_frequencies = self.sample_rate as SampleReal / fs_real;
+        // expected phase advance of bin 1 over one hop
+        let expected = (TAU / (over_sampling as SampleReal)) * self.padding_ratio();
+        let mean_expected = expected / bin_frequencies;
+
+        self.fft_cplx.fill(COMPLEX_ZERO);
+
+        for k in 0..half_fft_size {
+            self.phase_sum[k] += mean_expected * self.synthesized_frequency[k];
+
+            // around the accumulated phase, so that it doesn't drift
+            let mut phase = self.phase_sum[k];
+            if self.phase_dispersion > 0.0 {
+                let jitter = 2.0 * self.rng.next_f32() - 1.0;
+                phase += self.phase_dispersion * PI * jitter;
+            }
+
+            let (sin, cos) = phase.sin_cos();
+            let magnitude = self.synthesized_magnitude[k];
+
+            self.fft_cplx[k].im = sin * magnitude;
+            self.fft_cplx[k].re = cos * magnitude;
+        }
+    }
+}
+
+/// Estimates the frequency of a (mostly) sinusoidal signal from the
+/// interpolated positions of its rising zero crossings.
+fn zero_crossing_frequency(signal: &[SampleReal], sample_rate: SampleReal) -> Option<SampleReal> {
+    let mut first = None;
+    let mut last = 0.0;
+    let mut periods = 0;
+
+    for i in 1..signal.len() {
+        let (a, b) = (signal[i - 1], signal[i]);
+        if a < 0.0 && b >= 0.0 {
+            let crossing = (i - 1) as SampleReal + a / (a - b);
+            match first {
+                None => first = Some(crossing),
+                Some(_) => periods += 1,
+            }
+            last = crossing;
+        }
+    }
+
+    let span = last - first?;
+    match periods {
+        0 => None,
+        _ => Some(sample_rate * (periods as SampleReal) / span),
+    }
+}
+
+/// `1.0` for bin `k` within `band` (low, high), falling to `0.0`
+/// at [`BAND_EDGE_BINS`] past its edges
+fn band_weight(k: SampleReal, (low, high): (SampleReal, SampleReal)) -> SampleReal {
+    if low > high {
+        return 0.0;
+    }
+
+    let distance = (low - k).max(k - high).max(0.0);
+    match distance < BAND_EDGE_BINS {
+        true => 0.5 + 0.5 * (PI * distance / BAND_EDGE_BINS).cos(),
+        false => 0.0,
+    }
+}
+
+/// Raised cosine rising from `0.0` to `1.0` over `len` samples
+fn loop_fade_gain(j: usize, len: usize) -> SampleReal {
+    let position = (j as SampleReal + 0.5) / (len as SampleReal);
+    0.5 - 0.5 * (PI * position).cos()
+}
+
+fn db_to_gain(db: SampleReal) -> SampleReal {
+    10.0_f32.powf(db / 20.0)
+}
+
+/// Geometric mean over arithmetic mean, with magnitudes floored
+/// to `floor` to avoid `ln(0)`
+fn spectral_flatness(magnitudes: impl Iterator<Item = SampleReal>, floor: SampleReal) -> SampleReal {
+    let (mut log_sum, mut sum, mut count) = (0.0, 0.0, 0);
+    for magnitude in magnitudes {
+        let magnitude = magnitude.max(floor);
+        log_sum += magnitude.ln();
+        sum += magnitude;
+        count += 1;
+    }
+
+    match count {
+        0 => 1.0,
+        // rounding can push uniform spectra slightly above 1
+        _ => ((log_sum / count as SampleReal).exp() / (sum / count as SampleReal)).min(1.0),
+    }
+}
+
+/// Identity below half the ceiling, then a tanh curve
+/// asymptotically reaching it
+fn soft_limit(sample: SampleReal, ceiling: SampleReal) -> SampleReal {
+    let knee = ceiling * 0.5;
+    let level = sample.abs();
+    match level > knee {
+        true => sample.signum() * (knee + (ceiling - knee) * ((level - knee) / (ceiling - knee)).tanh()),
+        false => sample,
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{BinMapping, PhaseMode, PitchShifterConfig, QuantMode, SampleReal, SynthesisScaling};
+
+/// Snapshot of every processing option of a [`PitchShifter`], as
+/// returned by [`PitchShifter::options`].
+///
+/// It's meant for logging which settings produced an output, e.g.
+/// in bug reports. Disabled features show their neutral value
+/// (`0.0`, `false` or `None`), the same as their setter's default.
+/// Fields are added as new options appear, so it can't be built
+/// outside this crate.
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::options`]: crate::PitchShifter::options
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ProcessingOptions {
+    /// Frame size, windows, zero padding & stored `over_sampling`
+    pub config: PitchShifterConfig,
+    /// Length of the FFTs, in samples
+    pub fft_size: usize,
+    /// Stored shift, in semitones, see [`PitchShifter::set_shift`](crate::PitchShifter::set_shift)
+    pub shift: SampleReal,
+    /// See [`PitchShifter::set_bin_mapping`](crate::PitchShifter::set_bin_mapping)
+    pub bin_mapping: BinMapping,
+    /// See [`PitchShifter::set_bypass_on_unity`](crate::PitchShifter::set_bypass_on_unity)
+    pub bypass_on_unity: bool,
+    /// See [`PitchShifter::set_magnitude_floor`](crate::PitchShifter::set_magnitude_floor)
+    pub magnitude_floor: SampleReal,
+    /// See [`PitchShifter::set_energy_preservation`](crate::PitchShifter::set_energy_preservation)
+    pub energy_preservation: bool,
+    /// See [`PitchShifter::set_magnitude_smoothing`](crate::PitchShifter::set_magnitude_smoothing)
+    pub magnitude_smoothing: SampleReal,
+    /// Band (low, high) in Hz and reduction in dB, see
+    /// [`PitchShifter::set_deess`](crate::PitchShifter::set_deess)
+    pub deess: Option<((SampleReal, SampleReal), SampleReal)>,
+    /// Threshold in dBFS, ratio & knee in dB, see
+    /// [`PitchShifter::set_expander`](crate::PitchShifter::set_expander)
+    pub expander: Option<(SampleReal, SampleReal, SampleReal)>,
+    /// In dB per octave, see [`PitchShifter::set_spectral_tilt`](crate::PitchShifter::set_spectral_tilt)
+    pub spectral_tilt: SampleReal,
+    /// See [`PitchShifter::set_harmonic_excite`](crate::PitchShifter::set_harmonic_excite)
+    pub harmonic_excite: SampleReal,
+    /// Ceiling of the output limiter, see
+    /// [`PitchShifter::set_output_limiter`](crate::PitchShifter::set_output_limiter)
+    pub limiter_ceiling: Option<SampleReal>,
+    /// See [`PitchShifter::set_clip_ceiling`](crate::PitchShifter::set_clip_ceiling)
+    pub clip_ceiling: SampleReal,
+    /// See [`PitchShifter::set_wide_accumulator`](crate::PitchShifter::set_wide_accumulator)
+    pub wide_accumulator: bool,
+    /// See [`PitchShifter::set_start_offset`](crate::PitchShifter::set_start_offset)
+    pub start_offset: usize,
+    /// See [`PitchShifter::set_auto_gain_match`](crate::PitchShifter::set_auto_gain_match)
+    pub auto_gain_match: bool,
+    /// See [`PitchShifter::set_frame_gain_normalization`](crate::PitchShifter::set_frame_gain_normalization)
+    pub frame_gain_normalization: bool,
+    /// See [`PitchShifter::set_first_frame_phase_reset`](crate::PitchShifter::set_first_frame_phase_reset)
+    pub first_frame_phase_reset: bool,
+    /// See [`PitchShifter::set_highpass_hz`](crate::PitchShifter::set_highpass_hz)
+    pub highpass_hz: Option<SampleReal>,
+    /// See [`PitchShifter::set_emphasis`](crate::PitchShifter::set_emphasis)
+    pub emphasis: Option<SampleReal>,
+    /// See [`PitchShifter::set_shift_quantization`](crate::PitchShifter::set_shift_quantization)
+    pub quantization: QuantMode,
+    /// See [`PitchShifter::set_phase_mode`](crate::PitchShifter::set_phase_mode)
+    pub phase_mode: PhaseMode,
+    /// See [`PitchShifter::set_phase_dispersion`](crate::PitchShifter::set_phase_dispersion)
+    pub phase_dispersion: SampleReal,
+    /// See [`PitchShifter::set_reverse_processing`](crate::PitchShifter::set_reverse_processing)
+    pub reverse_processing: bool,
+    /// See [`PitchShifter::set_fine_tune`](crate::PitchShifter::set_fine_tune)
+    pub fine_tune: bool,
+    /// Override of the synthesis factors, see
+    /// [`PitchShifter::set_synthesis_scaling`](crate::PitchShifter::set_synthesis_scaling)
+    pub synthesis_scaling: Option<SynthesisScaling>,
+}
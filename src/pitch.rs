@@ -0,0 +1,35 @@
+//! Conversions between pitch intervals and frequency ratios,
+//! matching what [`PitchShifter`](crate::PitchShifter) uses for its
+//! `shift` arguments.
+//!
+//! A semitone is a twelfth of an octave, and a cent is a hundredth
+//! of a semitone: shifting by `12.0` semitones (`1200.0` cents)
+//! doubles frequencies.
+
+use crate::SampleReal;
+
+/// Frequency ratio of an interval of `semitones`, i.e. `2^(semitones / 12)`
+pub fn semitones_to_ratio(semitones: SampleReal) -> SampleReal {
+    2.0_f32.powf(semitones / 12.0)
+}
+
+/// Interval, in semitones, of a frequency `ratio`; the inverse of
+/// [`semitones_to_ratio`].
+///
+/// A `ratio` of zero or less gives `-inf` or NaN.
+pub fn ratio_to_semitones(ratio: SampleReal) -> SampleReal {
+    12.0 * ratio.log2()
+}
+
+/// Frequency ratio of an interval of `cents`, i.e. `2^(cents / 1200)`
+pub fn cents_to_ratio(cents: SampleReal) -> SampleReal {
+    2.0_f32.powf(cents / 1200.0)
+}
+
+/// Interval, in cents, of a frequency `ratio`; the inverse of
+/// [`cents_to_ratio`].
+///
+/// A `ratio` of zero or less gives `-inf` or NaN.
+pub fn ratio_to_cents(ratio: SampleReal) -> SampleReal {
+    1200.0 * ratio.log2()
+}
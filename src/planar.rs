@@ -0,0 +1,32 @@
+use crate::{Complex, SampleReal};
+
+/// Packs a split (planar) complex spectrum into an interleaved one.
+///
+/// This crate, like `rustfft` and `realfft`, stores spectra as
+/// [`Complex`] values, i.e. interleaved: `re0, im0, re1, im1, ...`.
+/// Many GPU and DSP libraries use a planar layout instead, with all
+/// real parts in one buffer (`re`) and all imaginary parts in
+/// another (`im`). Converting is a single copy of each bin, cheap
+/// next to processing the spectrum, which takes a polar conversion
+/// of every bin.
+///
+/// Panics unless `re`, `im` and `spectrum` have the same length.
+pub fn planar_to_complex(re: &[SampleReal], im: &[SampleReal], spectrum: &mut [Complex<SampleReal>]) {
+    assert_eq!(re.len(), spectrum.len(), "re must have as many values as spectrum");
+    assert_eq!(im.len(), spectrum.len(), "im must have as many values as spectrum");
+
+    let parts = re.iter().zip(im);
+    spectrum.iter_mut().zip(parts).for_each(|(c, (re, im))| *c = Complex::new(*re, *im));
+}
+
+/// Splits an interleaved complex spectrum into planar real and
+/// imaginary parts, see [`planar_to_complex`].
+///
+/// Panics unless `spectrum`, `re` and `im` have the same length.
+pub fn complex_to_planar(spectrum: &[Complex<SampleReal>], re: &mut [SampleReal], im: &mut [SampleReal]) {
+    assert_eq!(re.len(), spectrum.len(), "re must have as many values as spectrum");
+    assert_eq!(im.len(), spectrum.len(), "im must have as many values as spectrum");
+
+    let parts = re.iter_mut().zip(im);
+    spectrum.iter().zip(parts).for_each(|(c, (re, im))| (*re, *im) = (c.re, c.im));
+}
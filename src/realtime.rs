@@ -0,0 +1,126 @@
+use crate::SampleReal;
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Lock-free handle changing some settings of a [`PitchShifter`]
+/// from another thread, see [`PitchShifter::realtime_parameters`].
+///
+/// Setters only store atomics, they never block nor allocate; the
+/// shifter picks new values up at its next frame boundary, i.e.
+/// within `frame_size / over_sampling` samples, without locking or
+/// allocating either. Each value is applied atomically, but values
+/// changed together may be picked up on different frames.
+///
+/// Only settings which can change mid-stream without allocation
+/// are available here; the others (windows, tilt, frame size, ...)
+/// must be changed on the shifter itself, outside of the audio
+/// thread.
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::realtime_parameters`]: crate::PitchShifter::realtime_parameters
+#[derive(Clone, Debug)]
+pub struct RealtimeParameters {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    // bumped after each change
+    generation: AtomicU64,
+    shift: AtomicU32,
+    harmonic_excite: AtomicU32,
+    magnitude_smoothing: AtomicU32,
+    limiter_ceiling: AtomicU32,
+    energy_preservation: AtomicBool,
+    bypass_on_unity: AtomicBool,
+}
+
+/// Values of a [`RealtimeParameters`], as picked up by the shifter
+pub(crate) struct Snapshot {
+    pub shift: SampleReal,
+    pub harmonic_excite: SampleReal,
+    pub magnitude_smoothing: SampleReal,
+    pub limiter_ceiling: SampleReal,
+    pub energy_preservation: bool,
+    pub bypass_on_unity: bool,
+}
+
+impl RealtimeParameters {
+    pub(crate) fn new(initial: Snapshot) -> Self {
+        let float = |value: SampleReal| AtomicU32::new(value.to_bits());
+        Self {
+            shared: Arc::new(Shared {
+                generation: AtomicU64::new(0),
+                shift: float(initial.shift),
+                harmonic_excite: float(initial.harmonic_excite),
+                magnitude_smoothing: float(initial.magnitude_smoothing),
+                limiter_ceiling: float(initial.limiter_ceiling),
+                energy_preservation: AtomicBool::new(initial.energy_preservation),
+                bypass_on_unity: AtomicBool::new(initial.bypass_on_unity),
+            }),
+        }
+    }
+
+    /// Returns the current values if they changed since `generation`,
+    /// which is then updated
+    pub(crate) fn poll(&self, generation: &mut u64) -> Option<Snapshot> {
+        let shared = &*self.shared;
+        let current = shared.generation.load(Ordering::Acquire);
+        if current == *generation {
+            return None;
+        }
+
+        *generation = current;
+        let float = |value: &AtomicU32| SampleReal::from_bits(value.load(Ordering::Relaxed));
+        Some(Snapshot {
+            shift: float(&shared.shift),
+            harmonic_excite: float(&shared.harmonic_excite),
+            magnitude_smoothing: float(&shared.magnitude_smoothing),
+            limiter_ceiling: float(&shared.limiter_ceiling),
+            energy_preservation: shared.energy_preservation.load(Ordering::Relaxed),
+            bypass_on_unity: shared.bypass_on_unity.load(Ordering::Relaxed),
+        })
+    }
+
+    fn store(&self, value: &AtomicU32, new: SampleReal) {
+        value.store(new.to_bits(), Ordering::Relaxed);
+        self.shared.generation.fetch_add(1, Ordering::Release);
+    }
+
+    fn store_bool(&self, value: &AtomicBool, new: bool) {
+        value.store(new, Ordering::Relaxed);
+        self.shared.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// See [`PitchShifter::set_shift`](crate::PitchShifter::set_shift);
+    /// like it, this only affects [`PitchShifter::push`](crate::PitchShifter::push).
+    pub fn set_shift(&self, shift: SampleReal) {
+        self.store(&self.shared.shift, shift);
+    }
+
+    /// See [`PitchShifter::set_harmonic_excite`](crate::PitchShifter::set_harmonic_excite)
+    pub fn set_harmonic_excite(&self, amount: SampleReal) {
+        self.store(&self.shared.harmonic_excite, amount);
+    }
+
+    /// See [`PitchShifter::set_magnitude_smoothing`](crate::PitchShifter::set_magnitude_smoothing)
+    pub fn set_magnitude_smoothing(&self, coeff: SampleReal) {
+        self.store(&self.shared.magnitude_smoothing, coeff);
+    }
+
+    /// See [`PitchShifter::set_output_limiter`](crate::PitchShifter::set_output_limiter)
+    pub fn set_output_limiter(&self, ceiling: SampleReal) {
+        self.store(&self.shared.limiter_ceiling, ceiling);
+    }
+
+    /// See [`PitchShifter::set_energy_preservation`](crate::PitchShifter::set_energy_preservation)
+    pub fn set_energy_preservation(&self, preserve: bool) {
+        self.store_bool(&self.shared.energy_preservation, preserve);
+    }
+
+    /// See [`PitchShifter::set_bypass_on_unity`](crate::PitchShifter::set_bypass_on_unity)
+    pub fn set_bypass_on_unity(&self, bypass: bool) {
+        self.store_bool(&self.shared.bypass_on_unity, bypass);
+    }
+}
@@ -0,0 +1,44 @@
+/// Small, deterministic pseudo-random generator (SplitMix64) used
+/// by every stochastic feature of [`PitchShifter`].
+///
+/// It's not suitable for cryptography, only for audio.
+/// See [`PitchShifter::set_rng_seed`].
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::set_rng_seed`]: crate::PitchShifter::set_rng_seed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+// used by default, so that output is reproducible
+const DEFAULT_SEED: u64 = 0x0000_5EED_0FD1_CE00;
+
+impl Rng {
+    /// Two generators built from the same seed produce
+    /// the same sequence.
+    pub fn from_seed(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Uniformly distributed over all `u64` values
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in `0.0..1.0`
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits: as many as an f32 mantissa holds
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::from_seed(DEFAULT_SEED)
+    }
+}
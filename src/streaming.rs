@@ -0,0 +1,140 @@
+use crate::{PitchShifter, SampleReal};
+
+/// Push-based wrapper around a [`PitchShifter`], for streams which
+/// arrive in chunks of any size and leave in blocks of a fixed one.
+///
+/// Input is given to [`StreamingShifter::write`], in any amount,
+/// and shifted right away (with the shift & `over_sampling` stored
+/// in the shifter, see [`PitchShifter::set_shift`] and
+/// [`PitchShifter::set_over_sampling`]). Output is queued until a
+/// whole block is ready, which [`StreamingShifter::read`] then
+/// returns. Every input sample produces one output sample, so
+/// blocks come out at the pace they're written, but the shifter's
+/// latency (see [`PitchShifter::latency_samples`]) is trimmed: the
+/// output is aligned with the input, and the first block is ready
+/// once `block_size` plus that many samples have been written.
+/// [`StreamingShifter::finish`] flushes the last ones at the end of
+/// the stream.
+///
+/// ```
+/// use pitch_shift::{PitchShifter, StreamingShifter};
+///
+/// let mut shifter = PitchShifter::with_window_samples(1024, 44100);
+/// shifter.set_shift(3.0);
+/// let mut stream = StreamingShifter::new(shifter, 256);
+///
+/// let mut out = Vec::new();
+/// for chunk in vec![0.0; 10000].chunks(700) {
+///     stream.write(chunk);
+///     while let Some(block) = stream.read() {
+///         out.extend_from_slice(block);
+///     }
+/// }
+///
+/// stream.finish();
+/// while let Some(block) = stream.read() {
+///     out.extend_from_slice(block);
+/// }
+/// assert_eq!(out.len(), 10000);
+/// ```
+///
+/// [`PitchShifter`]: crate::PitchShifter
+/// [`PitchShifter::set_shift`]: crate::PitchShifter::set_shift
+/// [`PitchShifter::set_over_sampling`]: crate::PitchShifter::set_over_sampling
+/// [`PitchShifter::latency_samples`]: crate::PitchShifter::latency_samples
+pub struct StreamingShifter {
+    shifter: PitchShifter,
+    block: Vec<SampleReal>,
+    // leading output samples left to drop, `None` before the first write
+    latency: Option<usize>,
+    // samples left to read, once finished
+    remaining: Option<usize>,
+}
+
+impl StreamingShifter {
+    /// Wraps `shifter`, reading output in blocks of `block_size`
+    /// samples.
+    ///
+    /// The shifter is reset. Panics if `block_size` is zero.
+    pub fn new(mut shifter: PitchShifter, block_size: usize) -> Self {
+        assert!(block_size > 0, "block_size must be greater than zero");
+        shifter.reset();
+        Self {
+            shifter,
+            block: vec![0.0; block_size],
+            latency: None,
+            remaining: None,
+        }
+    }
+
+    /// Shifts `input`, queuing the output for
+    /// [`StreamingShifter::read`]. Input written after
+    /// [`StreamingShifter::finish`] starts a new stream.
+    pub fn write(&mut self, input: &[SampleReal]) {
+        if self.remaining.is_some() {
+            self.shifter.reset();
+            self.latency = None;
+            self.remaining = None;
+        }
+
+        let over_sampling = self.shifter.over_sampling();
+        self.latency.get_or_insert(self.shifter.latency_samples(over_sampling));
+        self.shifter.push(input);
+    }
+
+    /// Next block of output, if it's complete.
+    ///
+    /// Call it until it returns `None` after each
+    /// [`StreamingShifter::write`]: queued output is only dropped by
+    /// reading it. After [`StreamingShifter::finish`], the last block
+    /// can be shorter than `block_size`.
+    pub fn read(&mut self) -> Option<&[SampleReal]> {
+        let latency = self.latency.as_mut()?;
+        while *latency > 0 && self.shifter.available() > 0 {
+            let len = self.block.len().min(*latency);
+            *latency -= self.shifter.pull(&mut self.block[..len]);
+        }
+
+        let block_size = self.block.len();
+        let len = match self.remaining {
+            Some(remaining) => block_size.min(remaining),
+            None => block_size,
+        };
+
+        if len == 0 || *latency > 0 || self.shifter.available() < len {
+            return None;
+        }
+
+        self.shifter.pull(&mut self.block[..len]);
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= len;
+        }
+        Some(&self.block[..len])
+    }
+
+    /// Ends the stream, flushing the samples still held by the
+    /// shifter so that [`StreamingShifter::read`] returns as many
+    /// samples as were written in total. Does nothing if the
+    /// stream is already finished, or empty.
+    pub fn finish(&mut self) {
+        let Some(latency) = self.latency.filter(|_| self.remaining.is_none()) else {
+            return;
+        };
+
+        let over_sampling = self.shifter.over_sampling();
+        self.shifter.push(&vec![0.0; self.shifter.latency_samples(over_sampling)]);
+        // the leading silence left to drop is made up for by the flush
+        self.remaining = Some(self.shifter.available() - latency);
+    }
+
+    /// The wrapped shifter, e.g. to change its shift
+    pub fn shifter_mut(&mut self) -> &mut PitchShifter {
+        &mut self.shifter
+    }
+
+    /// Unwraps the shifter; output still queued is kept in it, see
+    /// [`PitchShifter::pull`]
+    pub fn into_inner(self) -> PitchShifter {
+        self.shifter
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{PitchShifter, PitchShifterConfig, SampleReal, WindowFunction};
+
+use hound::{SampleFormat, WavReader, WavWriter};
+
+use std::io;
+use std::path::Path;
+
+/// Shifts the pitch of a whole WAV file by `shift` semitones and
+/// writes the result to another one (requires the `wav` feature).
+///
+/// Integer PCM files from 8 to 32 bits and 32-bit float files are
+/// supported; the output has the same format, sample rate and
+/// channels as the input. Each channel is processed separately,
+/// with a shifter built from `config`, as in
+/// [`PitchShifter::process_clip`]: the tail is flushed and the
+/// latency trimmed, so that the output is exactly as long as the
+/// input and aligned with it.
+///
+/// The window duration of `config` is kept, its frame size being
+/// scaled if the file's sample rate differs from
+/// [`PitchShifterConfig::sample_rate`]: a power of two stays one,
+/// with [`PitchShifterConfig::power_of_two_frame`] (2048 samples at
+/// 48 kHz remain 2048 at 44.1 kHz, rather than 1881), so that sizes
+/// the FFT backend requires or runs fastest remain so, while other
+/// sizes are scaled exactly. Integer samples are
+/// normalized to `-1.0..1.0`, so sample scale dependent settings
+/// see the same levels for every bit depth, and shifted samples
+/// past full scale are saturated rather than wrapped around.
+///
+/// Errors are those of [`hound`], e.g. for a file that can't be
+/// read or an unsupported format, and an [`io::ErrorKind::InvalidInput`]
+/// one if `config` can't be rescaled: a window too short for the
+/// file's sample rate, or a [`WindowFunction::Custom`] one.
+///
+/// [`io::ErrorKind::InvalidInput`]: std::io::ErrorKind::InvalidInput
+pub fn shift_wav_file<P, Q>(input_path: P, output_path: Q, shift: SampleReal, config: &PitchShifterConfig) -> hound::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut reader = WavReader::open(input_path)?;
+    let spec = reader.spec();
+
+    // full scale of integer samples
+    let scale = match spec.sample_format {
+        SampleFormat::Int => (1u64 << (spec.bits_per_sample - 1)) as SampleReal,
+        SampleFormat::Float => 1.0,
+    };
+    let samples = match spec.sample_format {
+        SampleFormat::Int => reader.samples::<i32>().map(|s| s.map(|s| s as SampleReal / scale)).collect(),
+        SampleFormat::Float => reader.samples::<f32>().collect::<hound::Result<Vec<_>>>(),
+    }?;
+
+    let channels = spec.channels as usize;
+    let config = rescaled_config(config, spec.sample_rate as usize)?;
+    let mut shifter = PitchShifter::from_config(&config);
+
+    let mut shifted = vec![0.0; samples.len()];
+    for channel in 0..channels {
+        let in_b: Vec<SampleReal> = samples.iter().skip(channel).step_by(channels).copied().collect();
+        let out_b = shifter.process_clip(config.over_sampling, shift, &in_b);
+        let out_samples = shifted.iter_mut().skip(channel).step_by(channels);
+        out_samples.zip(out_b).for_each(|(dst, src)| *dst = src);
+    }
+
+    let mut writer = WavWriter::create(output_path, spec)?;
+    for sample in shifted {
+        match spec.sample_format {
+            SampleFormat::Int => {
+                let sample = (sample * scale).round().clamp(-scale, scale - 1.0);
+                writer.write_sample(sample as i32)?;
+            },
+            SampleFormat::Float => writer.write_sample(sample)?,
+        }
+    }
+    writer.finalize()
+}
+
+/// `config` with the same window duration at `sample_rate`
+fn rescaled_config(config: &PitchShifterConfig, sample_rate: usize) -> hound::Result<PitchShifterConfig> {
+    let mut config = config.clone();
+    if sample_rate == config.sample_rate {
+        return Ok(config);
+    }
+
+    let invalid = |message| Err(hound::Error::IoError(io::Error::new(io::ErrorKind::InvalidInput, message)));
+    if [&config.analysis_window, &config.synthesis_window].iter().any(|w| matches!(w, WindowFunction::Custom(_))) {
+        return invalid("custom windows can't be rescaled to the file's sample rate");
+    }
+
+    let power_of_two = config.frame_size.is_power_of_two();
+    config.frame_size = config.frame_size * sample_rate / config.sample_rate;
+    config.sample_rate = sample_rate;
+    if config.frame_size < config.over_sampling.max(1) {
+        return invalid("the window is too short for the file's sample rate");
+    }
+
+    if power_of_two {
+        config = config.power_of_two_frame();
+    }
+    Ok(config)
+}
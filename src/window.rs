@@ -0,0 +1,225 @@
+use crate::SampleReal;
+
+use std::f32::consts::TAU;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// Shape of the windows applied to each frame, see
+/// [`PitchShifter::set_analysis_window`] &
+/// [`PitchShifter::set_synthesis_window`]
+///
+/// [`PitchShifter::set_analysis_window`]: crate::PitchShifter::set_analysis_window
+/// [`PitchShifter::set_synthesis_window`]: crate::PitchShifter::set_synthesis_window
+#[derive(Clone, Debug)]
+pub enum WindowFunction {
+    /// Raised cosine reaching zero at both ends; the default.
+    Hann,
+    /// Raised cosine on a pedestal: lower first sidelobe than
+    /// Hann, but slower sidelobe decay.
+    Hamming,
+    /// Three-term cosine window: wider main lobe than Hann,
+    /// much lower sidelobes.
+    Blackman,
+    /// Flat in the middle, with Hann-shaped tapers covering a
+    /// proportion `alpha` (in `0.0..=1.0`) of the frame: `0.0` is
+    /// rectangular, `1.0` is Hann.
+    ///
+    /// Larger tapers leak less, smaller ones have a narrower
+    /// main lobe.
+    Tukey(SampleReal),
+    /// Five-term cosine window with a very flat main lobe: peak
+    /// magnitudes read from a spectrum are accurate whatever the
+    /// frequency of the partial within its bin, at the cost of a
+    /// very wide main lobe. Best for amplitude measurement.
+    FlatTop,
+    /// Bessel-based window whose `beta` (at least `0.0`) trades
+    /// main lobe width for sidelobe level: `0.0` is rectangular,
+    /// `5.0` has the main lobe of Hann with sidelobes at -37 dB
+    /// (instead of -31 dB), `6.0` the sidelobes of Hamming (-44 dB)
+    /// and `8.6` is close to Blackman (-63 dB). Sidelobes reach
+    /// -90 dB at `12.0`, with a main lobe twice as wide as Hann's.
+    Kaiser(SampleReal),
+    /// Your own coefficients, which must be exactly as long as
+    /// the frame.
+    Custom(Vec<SampleReal>),
+}
+
+// Coefficients are compared & hashed bitwise, so that windows
+// can be used in cache keys (see `PitchShifterConfig`).
+impl PartialEq for WindowFunction {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Custom(a), Self::Custom(b)) => {
+                let bits = |w: &[SampleReal]| w.iter().map(|c| c.to_bits()).collect::<Vec<_>>();
+                bits(a) == bits(b)
+            },
+            (Self::Tukey(a), Self::Tukey(b)) => a.to_bits() == b.to_bits(),
+            (Self::Kaiser(a), Self::Kaiser(b)) => a.to_bits() == b.to_bits(),
+            (a, b) => core::mem::discriminant(a) == core::mem::discriminant(b),
+        }
+    }
+}
+
+impl Eq for WindowFunction {}
+
+impl Hash for WindowFunction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Custom(coefficients) => coefficients.iter().for_each(|c| c.to_bits().hash(state)),
+            Self::Tukey(alpha) => alpha.to_bits().hash(state),
+            Self::Kaiser(beta) => beta.to_bits().hash(state),
+            _ => (),
+        }
+    }
+}
+
+impl WindowFunction {
+    /// Computes the `len` coefficients of this window.
+    ///
+    /// Windows are periodic (the sample following the last one
+    /// would equal the first), which is what overlap-add needs.
+    ///
+    /// Panics if this is a [`WindowFunction::Custom`] window
+    /// of a different length, a [`WindowFunction::Tukey`] window
+    /// with `alpha` outside of `0.0..=1.0`, or a
+    /// [`WindowFunction::Kaiser`] window with a negative `beta`.
+    pub fn coefficients(&self, len: usize) -> Vec<SampleReal> {
+        let len_real = len as SampleReal;
+        let cosine = |k: usize, harmonic: SampleReal| (harmonic * TAU * (k as SampleReal) / len_real).cos();
+
+        match self {
+            Self::Hann => (0..len).map(|k| -0.5 * cosine(k, 1.0) + 0.5).collect(),
+            Self::Hamming => (0..len).map(|k| 0.54 - 0.46 * cosine(k, 1.0)).collect(),
+            Self::Blackman => (0..len).map(|k| 0.42 - 0.5 * cosine(k, 1.0) + 0.08 * cosine(k, 2.0)).collect(),
+            Self::Tukey(alpha) => {
+                assert!((0.0..=1.0).contains(alpha), "tukey window alpha must be in 0.0..=1.0");
+                (0..len).map(|k| {
+                    let position = (k as SampleReal) / len_real;
+                    // distance to the closest edge, in 0.0..=0.5
+                    let edge = position.min(1.0 - position);
+                    match edge < alpha * 0.5 {
+                        true => 0.5 - 0.5 * (TAU * edge / alpha).cos(),
+                        false => 1.0,
+                    }
+                }).collect()
+            },
+            Self::FlatTop => (0..len).map(|k| {
+                0.21557895
+                    - 0.41663158 * cosine(k, 1.0)
+                    + 0.27726316 * cosine(k, 2.0)
+                    - 0.08357895 * cosine(k, 3.0)
+                    + 0.006947368 * cosine(k, 4.0)
+            }).collect(),
+            Self::Kaiser(beta) => {
+                assert!(*beta >= 0.0, "kaiser window beta must be at least 0.0");
+                let beta = *beta as f64;
+                let scale = bessel_i0(beta);
+                (0..len).map(|k| {
+                    // in -1.0..1.0 over the frame
+                    let x = 2.0 * (k as f64) / (len as f64) - 1.0;
+                    (bessel_i0(beta * (1.0 - x * x).sqrt()) / scale) as SampleReal
+                }).collect()
+            },
+            Self::Custom(coefficients) => {
+                assert_eq!(coefficients.len(), len, "custom window length must equal the frame size");
+                coefficients.clone()
+            },
+        }
+    }
+}
+
+/// Window coefficients and level compensation for one frame size,
+/// computed once and shared by every shifter built from them with
+/// [`PitchShifter::with_window_tables`].
+///
+/// Building a shifter otherwise evaluates its windows (and the Hann
+/// reference of the compensation) sample by sample; for many voices
+/// of the same configuration, build the tables once and clone them
+/// (cloning only bumps reference counts):
+///
+/// ```
+/// use pitch_shift::{PitchShifter, WindowFunction, WindowTables};
+///
+/// let tables = WindowTables::new(2048, WindowFunction::Hann, WindowFunction::Blackman);
+/// let voices: Vec<_> = (0..16).map(|_| PitchShifter::with_window_tables(&tables, 48000)).collect();
+/// ```
+///
+/// [`PitchShifter::window_tables`] returns the tables of an
+/// existing shifter.
+///
+/// [`PitchShifter::with_window_tables`]: crate::PitchShifter::with_window_tables
+/// [`PitchShifter::window_tables`]: crate::PitchShifter::window_tables
+#[derive(Clone, Debug)]
+pub struct WindowTables {
+    pub(crate) frame_size: usize,
+    pub(crate) analysis_function: WindowFunction,
+    pub(crate) synthesis_function: WindowFunction,
+    pub(crate) analysis: Arc<[SampleReal]>,
+    pub(crate) synthesis: Arc<[SampleReal]>,
+    pub(crate) compensation: SampleReal,
+}
+
+impl WindowTables {
+    /// Computes the tables of frames of `samples` samples, rounded
+    /// up to an even number like in
+    /// [`PitchShifter::with_window_samples`].
+    ///
+    /// Panics if `samples` is zero, or if a window is invalid for
+    /// this frame size (see [`WindowFunction::coefficients`]).
+    ///
+    /// [`PitchShifter::with_window_samples`]: crate::PitchShifter::with_window_samples
+    pub fn new(samples: usize, analysis: WindowFunction, synthesis: WindowFunction) -> Self {
+        assert!(samples > 0, "window must contain at least one sample");
+        let frame_size = samples + samples % 2;
+
+        let analysis_window: Arc<[SampleReal]> = analysis.coefficients(frame_size).into();
+        let synthesis_window = match synthesis == analysis {
+            true => analysis_window.clone(),
+            false => synthesis.coefficients(frame_size).into(),
+        };
+
+        Self {
+            frame_size,
+            compensation: compensation(&analysis_window, &synthesis_window),
+            analysis_function: analysis,
+            synthesis_function: synthesis,
+            analysis: analysis_window,
+            synthesis: synthesis_window,
+        }
+    }
+
+    /// Length of the windows, in samples
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+}
+
+/// Gain bringing the overlap-add of `analysis` & `synthesis` to
+/// the level of the Hann/Hann pair, or `1.0` if their product sums
+/// to zero (e.g. an all-zero window), which no gain can bring there
+pub(crate) fn compensation(analysis: &[SampleReal], synthesis: &[SampleReal]) -> SampleReal {
+    let hann = WindowFunction::Hann.coefficients(analysis.len());
+    let reference: SampleReal = hann.iter().map(|w| w * w).sum();
+    let product: SampleReal = analysis.iter().zip(synthesis).map(|(a, s)| a * s).sum();
+    match product.is_normal() {
+        true => reference / product,
+        false => 1.0,
+    }
+}
+
+/// Zeroth order modified Bessel function of the first kind, from
+/// its power series
+fn bessel_i0(x: f64) -> f64 {
+    let half = x / 2.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut m = 1.0;
+    while term > sum * 1e-12 {
+        term *= (half / m) * (half / m);
+        sum += term;
+        m += 1.0;
+    }
+    sum
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::{assert_buffers_close, sine, UNITY_GAIN};
+use pitch_shift::PitchShifter;
+
+const SAMPLE_RATE: usize = 8000;
+const FRAME_SIZE: usize = 256;
+
+/// Processes `in_b` in blocks of varying sizes, per block and per
+/// sample, checking that both agree and stay finite
+fn check_over_sampling(over_sampling: usize) {
+    let in_b = sine(440.0, 3000, SAMPLE_RATE);
+
+    let mut blocks = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    let mut start = 0;
+    for size in [1, 7, 300, 255, 256, 257, 1000].iter().cycle() {
+        let end = (start + size).min(in_b.len());
+        shifter.shift_pitch(over_sampling, 3.0, &in_b[start..end], &mut blocks[start..end]);
+        start = end;
+        if start == in_b.len() {
+            break;
+        }
+    }
+
+    let mut samples = Vec::with_capacity(in_b.len());
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    shifter.shift_pitch_sink(over_sampling, 3.0, &in_b, |s| samples.push(s));
+
+    assert_buffers_close(&blocks, &samples, 0.0);
+    assert!(blocks.iter().all(|s| s.is_finite()));
+    assert!(blocks[FRAME_SIZE * 2..].iter().any(|s| *s != 0.0));
+}
+
+#[test]
+fn over_sampling_of_one() {
+    check_over_sampling(1);
+}
+
+#[test]
+fn over_sampling_of_two() {
+    check_over_sampling(2);
+}
+
+#[test]
+fn over_sampling_of_frame_size() {
+    check_over_sampling(FRAME_SIZE);
+}
+
+#[test]
+fn over_sampling_changes_mid_stream() {
+    let in_b = sine(440.0, 500, SAMPLE_RATE);
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    for over_sampling in [4, 16, 2, FRAME_SIZE, 1, 8] {
+        shifter.shift_pitch(over_sampling, 3.0, &in_b, &mut out_b);
+        assert!(out_b.iter().all(|s| s.is_finite()));
+    }
+}
+
+#[test]
+fn over_sampling_changes_keep_continuity() {
+    let in_b = sine(250.0, 4000, SAMPLE_RATE);
+
+    for pair in [[4, 8], [8, 16], [4, 32]] {
+        let mut out_b = vec![0.0; in_b.len()];
+        let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+        let blocks = in_b.chunks(FRAME_SIZE).zip(out_b.chunks_mut(FRAME_SIZE));
+        for ((in_c, out_c), over_sampling) in blocks.zip(pair.iter().cycle()) {
+            shifter.shift_pitch(*over_sampling, 0.0, in_c, out_c);
+        }
+
+        let latency = shifter.latency_samples(pair[0]);
+        let steady = 2 * FRAME_SIZE..in_b.len();
+        let expected: Vec<f32> = in_b[steady.start - latency..steady.end - latency]
+            .iter()
+            .map(|s| s * UNITY_GAIN)
+            .collect();
+        assert_buffers_close(&out_b[steady], &expected, 0.08);
+    }
+}
+
+#[test]
+fn start_offset_with_over_sampling_of_one() {
+    let in_b: Vec<f32> = (0..1000).map(|i| (i as f32 * i as f32 * 1e-4).sin()).collect();
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    shifter.set_start_offset(100);
+
+    // frames are spotted by a change of the measured centroid
+    let mut frames = Vec::new();
+    let mut centroid = shifter.last_spectral_centroid();
+    let mut out_b = [0.0];
+    for (i, sample) in in_b.iter().enumerate() {
+        shifter.shift_pitch(1, 0.0, &[*sample], &mut out_b);
+        if shifter.last_spectral_centroid() != centroid {
+            centroid = shifter.last_spectral_centroid();
+            frames.push(i + 1);
+        }
+    }
+
+    // the grid moves by 100 samples, then stays put
+    assert_eq!(frames, [100, 356, 612, 868]);
+}
+
+#[test]
+fn block_sizes_with_even_work() {
+    // hops of 64 samples
+    let shifter = PitchShifter::with_window_samples(512, 8000);
+    for (block, supported) in [(64, true), (256, true), (16, true), (1, true), (48, false), (100, false), (0, false)] {
+        assert_eq!(shifter.supports_block_size(block, 8), supported, "block of {}", block);
+    }
+
+    assert!(!shifter.supports_block_size(64, 0));
+    assert!(!shifter.supports_block_size(64, 1024));
+}
+
+// the builtin backend pads every other size to a power of two
+#[cfg(feature = "realfft")]
+#[test]
+fn fast_fft_sizes() {
+    for (frame_size, fast) in [(2048, true), (2000, true), (2160, true), (2002, false), (2026, false)] {
+        let shifter = PitchShifter::with_window_samples(frame_size, 44100);
+        assert_eq!(shifter.is_fft_fast(), fast, "frame of {}", frame_size);
+    }
+
+    // 2206 samples, twice a prime
+    let mut shifter = PitchShifter::new(50, 44100);
+    assert!(!shifter.is_fft_fast());
+    shifter.set_zero_padding(true);
+    assert!(shifter.is_fft_fast());
+}
+
+#[test]
+fn hops_match_blocks() {
+    let in_b = sine(440.0, 3000, SAMPLE_RATE);
+    let mut expected = vec![0.0; in_b.len()];
+    PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE).shift_pitch(8, 3.0, &in_b, &mut expected);
+
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    let mut out_b = vec![0.0; in_b.len()];
+    for (in_c, out_c) in in_b.chunks_exact(32).zip(out_b.chunks_exact_mut(32)) {
+        assert_eq!(shifter.process_hop(8, 3.0, in_c, out_c), 32);
+    }
+
+    let hops = in_b.len() / 32 * 32;
+    assert_eq!(out_b[..hops], expected[..hops]);
+}
+
+#[test]
+fn shared_scratch_matches_internal_scratch() {
+    let in_b = sine(440.0, 3000, SAMPLE_RATE);
+    let mut expected = vec![0.0; in_b.len()];
+    PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE).shift_pitch(8, 3.0, &in_b, &mut expected);
+
+    // two voices of different sizes share one buffer
+    let mut scratch = Vec::new();
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    let mut other = PitchShifter::with_window_samples(FRAME_SIZE * 2, SAMPLE_RATE);
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut other_b = vec![0.0; 500];
+    for (in_c, out_c) in in_b.chunks(500).zip(out_b.chunks_mut(500)) {
+        shifter.process_with_scratch(8, 3.0, in_c, out_c, &mut scratch);
+        other.process_with_scratch(4, -5.0, in_c, &mut other_b[..in_c.len()], &mut scratch);
+    }
+    assert!(scratch.len() >= shifter.scratch_len().max(other.scratch_len()));
+    assert_eq!(out_b, expected);
+
+    // and the internal scratch comes back when needed
+    shifter.reset();
+    shifter.shift_pitch(8, 3.0, &in_b, &mut out_b);
+    assert_eq!(out_b, expected);
+}
+
+#[test]
+fn per_call_windows_match_set_windows() {
+    use pitch_shift::{PitchShiftError, WindowFunction};
+
+    let in_b = sine(440.0, 3000, SAMPLE_RATE);
+    let mut expected = vec![0.0; in_b.len()];
+    let mut reference = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    reference.set_analysis_window(WindowFunction::Blackman);
+    reference.shift_pitch(8, 3.0, &in_b, &mut expected);
+
+    let analysis = WindowFunction::Blackman.coefficients(FRAME_SIZE);
+    let synthesis = WindowFunction::Hann.coefficients(FRAME_SIZE);
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    let mut out_b = vec![0.0; in_b.len()];
+    for (in_c, out_c) in in_b.chunks(500).zip(out_b.chunks_mut(500)) {
+        shifter.shift_pitch_with_windows(8, 3.0, &analysis, &synthesis, in_c, out_c);
+    }
+    assert_eq!(out_b, expected);
+
+    // the shifter's own windows are left as they were
+    let mut plain = vec![0.0; in_b.len()];
+    PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE).shift_pitch(8, 3.0, &in_b, &mut plain);
+    shifter.reset();
+    shifter.shift_pitch(8, 3.0, &in_b, &mut out_b);
+    assert_eq!(out_b, plain);
+    assert_eq!(
+        shifter.try_shift_pitch_with_windows(8, 3.0, &analysis[1..], &synthesis, &in_b, &mut out_b),
+        Err(PitchShiftError::WindowLength { expected: FRAME_SIZE, analysis_len: FRAME_SIZE - 1, synthesis_len: FRAME_SIZE }),
+    );
+}
@@ -0,0 +1,76 @@
+#![cfg(all(feature = "builtin-fft", feature = "realfft"))]
+
+use pitch_shift::{BuiltinFftBackend, Complex, FftBackend, PitchShifter, RealFftBackend};
+
+use std::f32::consts::TAU;
+
+#[test]
+fn builtin_fft_matches_realfft() {
+    let mut builtin = BuiltinFftBackend::new();
+    let mut realfft = RealFftBackend::new();
+
+    for fft_size in (1..=12).map(|bits| 1 << bits) {
+        let signal: Vec<f32> = (0..fft_size).map(|i| (i as f32 * 0.37).sin() + (i % 7) as f32 * 0.1).collect();
+        let scratch_len = builtin.scratch_len(fft_size).max(realfft.scratch_len(fft_size));
+        let mut scratch = vec![Complex::new(0.0, 0.0); scratch_len];
+
+        let mut spectra = [vec![Complex::new(0.0, 0.0); fft_size / 2 + 1], vec![Complex::new(0.0, 0.0); fft_size / 2 + 1]];
+        builtin.forward(&mut signal.clone(), &mut spectra[0], &mut scratch);
+        realfft.forward(&mut signal.clone(), &mut spectra[1], &mut scratch);
+        let tolerance = 1e-5 * fft_size as f32;
+        for (b, r) in spectra[0].iter().zip(&spectra[1]) {
+            assert!((b - r).norm() < tolerance, "size {}: {} vs {}", fft_size, b, r);
+        }
+
+        let mut outputs = [vec![0.0; fft_size], vec![0.0; fft_size]];
+        builtin.inverse(&mut spectra[0].clone(), &mut outputs[0], &mut scratch);
+        realfft.inverse(&mut spectra[1], &mut outputs[1], &mut scratch);
+        for (b, r) in outputs[0].iter().zip(&outputs[1]) {
+            assert!((b - r).abs() < tolerance, "size {}: {} vs {}", fft_size, b, r);
+        }
+    }
+}
+
+#[test]
+fn builtin_fft_shifts_like_realfft() {
+    let sample_rate = 8000;
+    let in_b: Vec<f32> = (0..sample_rate)
+        .map(|i| (TAU * 440.0 * i as f32 / sample_rate as f32).sin())
+        .collect();
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    let expected = shifter.process_clip(8, 3.0, &in_b);
+    shifter.set_fft_backend(BuiltinFftBackend::new());
+    let out_b = shifter.process_clip(8, 3.0, &in_b);
+    // phases of the fading partials of the last frame are
+    // sensitive to rounding
+    let steady = ..(in_b.len() - 512);
+    for (o, e) in out_b[steady].iter().zip(&expected[steady]) {
+        assert!((o - e).abs() < 1e-3, "{} vs {}", o, e);
+    }
+}
+
+#[test]
+fn builtin_fft_pads_other_sizes() {
+    let sample_rate = 8000;
+    let in_b: Vec<f32> = (0..sample_rate)
+        .map(|i| (TAU * 440.0 * i as f32 / sample_rate as f32).sin())
+        .collect();
+
+    let mut shifter = PitchShifter::with_window_samples(400, sample_rate);
+    shifter.set_zero_padding(true);
+    let expected = shifter.process_clip(8, 3.0, &in_b);
+
+    // padded whatever the setting, and unpadded again with realfft
+    shifter.set_zero_padding(false);
+    shifter.set_fft_backend(BuiltinFftBackend::new());
+    assert_eq!(shifter.fft_size(), 512);
+    let out_b = shifter.process_clip(8, 3.0, &in_b);
+    let steady = ..(in_b.len() - 400);
+    for (o, e) in out_b[steady].iter().zip(&expected[steady]) {
+        assert!((o - e).abs() < 1e-3, "{} vs {}", o, e);
+    }
+
+    shifter.set_fft_backend(RealFftBackend::new());
+    assert_eq!(shifter.fft_size(), 400);
+}
@@ -0,0 +1,100 @@
+//! Helpers shared by integration tests
+
+// each test crate uses a different subset
+#![allow(dead_code)]
+
+use std::f32::consts::TAU;
+
+/// Level of the vocoder at unity, with Hann windows
+pub const UNITY_GAIN: f32 = 0.7471;
+
+/// Largest absolute difference between two buffers
+#[derive(Copy, Clone, Debug)]
+pub struct Deviation {
+    /// Where it happens
+    pub index: usize,
+    /// How large it is
+    pub max: f32,
+}
+
+/// Compares two buffers of the same length, sample by sample
+pub fn max_deviation(actual: &[f32], expected: &[f32]) -> Deviation {
+    assert_eq!(actual.len(), expected.len(), "compared buffers have different lengths");
+    let mut deviation = Deviation { index: 0, max: 0.0 };
+    for (index, (a, e)) in actual.iter().zip(expected).enumerate() {
+        let difference = (a - e).abs();
+        // NaNs count as infinitely far
+        let difference = if difference.is_nan() { f32::INFINITY } else { difference };
+        if difference > deviation.max {
+            deviation = Deviation { index, max: difference };
+        }
+    }
+    deviation
+}
+
+/// Panics, reporting the worst sample, if any sample of `actual`
+/// differs from `expected` by more than `tolerance`
+pub fn assert_buffers_close(actual: &[f32], expected: &[f32], tolerance: f32) {
+    let Deviation { index, max } = max_deviation(actual, expected);
+    assert!(
+        max <= tolerance,
+        "buffers differ by {} at index {} ({} vs {}), tolerance is {}",
+        max, index, actual[index], expected[index], tolerance,
+    );
+}
+
+pub fn sine(hz: f32, len: usize, sample_rate: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| (TAU * hz * i as f32 / sample_rate as f32).sin())
+        .collect()
+}
+
+/// Frequency estimated from upward zero crossings
+pub fn crossing_frequency(samples: &[f32], sample_rate: usize) -> f32 {
+    let crossings = samples.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+    crossings as f32 * sample_rate as f32 / samples.len() as f32
+}
+
+/// Amplitude of the `hz` component of `samples`, from a single
+/// term of their discrete Fourier transform
+pub fn tone_level(samples: &[f32], hz: f32, sample_rate: usize) -> f32 {
+    let (mut re, mut im) = (0.0, 0.0);
+    for (i, sample) in samples.iter().enumerate() {
+        let phase = TAU * hz * i as f32 / sample_rate as f32;
+        re += sample * phase.cos();
+        im += sample * phase.sin();
+    }
+    2.0 * (re * re + im * im).sqrt() / samples.len() as f32
+}
+
+/// Logarithmic sine sweep from `from_hz` to `to_hz`, with its
+/// instantaneous frequency for each sample
+pub fn log_sweep(from_hz: f32, to_hz: f32, len: usize, sample_rate: usize) -> (Vec<f32>, Vec<f32>) {
+    let duration = len as f32 / sample_rate as f32;
+    let rate = (to_hz / from_hz).ln() / duration;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            let phase = TAU * from_hz * ((rate * t).exp() - 1.0) / rate;
+            (phase.sin(), from_hz * (rate * t).exp())
+        })
+        .unzip()
+}
+
+/// Gain from `in_b` to `out_b` in decibels, measured over
+/// consecutive segments of `segment` samples; each entry holds
+/// the mean instantaneous frequency of `hz` over the segment
+///
+/// The sweep must be slow enough for a segment to cover a narrow
+/// band, and `out_b` must be aligned with `in_b`.
+pub fn frequency_response(in_b: &[f32], out_b: &[f32], hz: &[f32], segment: usize) -> Vec<(f32, f32)> {
+    let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    in_b.chunks_exact(segment)
+        .zip(out_b.chunks_exact(segment))
+        .zip(hz.chunks_exact(segment))
+        .map(|((in_c, out_c), hz_c)| {
+            let center = hz_c.iter().sum::<f32>() / segment as f32;
+            (center, 20.0 * (rms(out_c) / rms(in_c)).log10())
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "dasp")]
+
+mod common;
+
+use common::{assert_buffers_close, sine};
+use pitch_shift::PitchShifter;
+
+use dasp_signal::Signal;
+
+#[test]
+fn integer_samples_match_float_processing() {
+    let sample_rate = 8000;
+    let in_b = sine(300.0, 4000, sample_rate);
+
+    let mut expected = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    shifter.shift_pitch(8, 4.0, &in_b, &mut expected);
+
+    let in_i16: Vec<i16> = in_b.iter().map(|s| (s * 16384.0) as i16).collect();
+    let mut out_i16 = vec![0; in_i16.len()];
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    shifter.shift_pitch_samples(8, 4.0, &in_i16, &mut out_i16);
+
+    let output: Vec<f32> = out_i16.iter().map(|s| *s as f32 / 16384.0).collect();
+    // a few steps of 16 bit quantization, on input and output
+    assert_buffers_close(&output, &expected, 4.0 / 16384.0);
+}
+
+#[test]
+fn signal_matches_buffer_processing() {
+    let sample_rate = 8000;
+    let in_b = sine(300.0, 4000, sample_rate);
+
+    let mut expected = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    shifter.shift_pitch(8, -3.0, &in_b, &mut expected);
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    let signal = dasp_signal::from_iter(in_b.iter().map(|s| [*s]));
+    let output: Vec<f32> = shifter.shift_signal(8, -3.0, signal).until_exhausted().map(|[s]| s).collect();
+
+    assert_eq!(output, expected);
+}
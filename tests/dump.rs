@@ -0,0 +1,30 @@
+#![cfg(feature = "debug_dump")]
+
+use pitch_shift::PitchShifter;
+
+#[test]
+fn dump_has_both_stages_of_every_frame() {
+    let path = std::env::temp_dir().join("pitch_shift_spectral_dump.csv");
+
+    let mut shifter = PitchShifter::with_window_samples(64, 8000);
+    shifter.start_spectral_dump(&path).unwrap();
+
+    // 4 hops, i.e. 4 frames
+    let in_b = vec![0.5; 64];
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter.shift_pitch(4, 3.0, &in_b, &mut out_b);
+    shifter.stop_spectral_dump().unwrap();
+
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("frame,stage,bin,magnitude,phase"));
+
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    // 33 bins per stage
+    assert_eq!(rows.len(), 4 * 2 * 33);
+    assert_eq!(rows[0][..3], ["1", "analysis", "0"]);
+    assert_eq!(rows[33][..3], ["1", "synthesis", "0"]);
+    assert_eq!(rows.last().unwrap()[..3], ["4", "synthesis", "32"]);
+}
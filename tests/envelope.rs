@@ -0,0 +1,28 @@
+mod common;
+
+use common::crossing_frequency;
+use pitch_shift::PitchShifter;
+
+use std::f32::consts::TAU;
+
+#[test]
+fn louder_input_is_shifted_further() {
+    let sample_rate = 8000;
+    // one second at -26 dB, then one at -1 dB
+    let in_b: Vec<f32> = (0..(2 * sample_rate))
+        .map(|i| {
+            let amplitude = if i < sample_rate { 0.05 } else { 0.9 };
+            amplitude * (TAU * 200.0 * i as f32 / sample_rate as f32).sin()
+        })
+        .collect();
+
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    shifter.shift_pitch_env_mod(8, 0.0, 12.0, &in_b, &mut out_b);
+
+    // envelopes of about 0.035 and 0.636: 0.4 & 7.6 semitones up
+    let quiet = crossing_frequency(&out_b[(sample_rate / 2)..sample_rate], sample_rate);
+    let loud = crossing_frequency(&out_b[(3 * sample_rate / 2)..], sample_rate);
+    assert!((quiet - 205.0).abs() < 8.0, "quiet part at {} Hz", quiet);
+    assert!((loud - 310.0).abs() < 8.0, "loud part at {} Hz", loud);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::sine;
+use pitch_shift::PitchShifter;
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+#[test]
+fn quiet_frames_are_turned_down() {
+    let sample_rate = 8000;
+    // half a second at -9 dBFS, then half a second at -53 dBFS
+    let tone = sine(300.0, sample_rate, sample_rate);
+    let in_b: Vec<f32> = tone.iter().enumerate().map(|(i, s)| match i < sample_rate / 2 {
+        true => 0.5 * s,
+        false => 0.003 * s,
+    }).collect();
+
+    let mut plain = PitchShifter::with_window_samples(512, sample_rate);
+    let expected = plain.process_clip(8, 3.0, &in_b);
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    shifter.set_expander(-40.0, 3.0, 6.0);
+    let out_b = shifter.process_clip(8, 3.0, &in_b);
+
+    let loud = 1000..3000;
+    let ratio = rms(&out_b[loud.clone()]) / rms(&expected[loud]);
+    assert!((ratio - 1.0).abs() < 1e-3, "loud part changed by {}", ratio);
+
+    // 13 dB below the threshold, at 1:3: 26 dB down
+    let quiet = 6000..7500;
+    let db = 20.0 * (rms(&out_b[quiet.clone()]) / rms(&expected[quiet])).log10();
+    assert!((db + 26.0).abs() < 1.5, "quiet part at {} dB", db);
+}
+
+#[test]
+fn expansion_recovers_from_digital_silence() {
+    let sample_rate = 8000;
+    let tone = sine(300.0, sample_rate, sample_rate);
+    let in_b: Vec<f32> = tone.iter().enumerate().map(|(i, s)| match i < sample_rate / 2 {
+        true => 0.0,
+        false => 0.5 * s,
+    }).collect();
+
+    let mut plain = PitchShifter::with_window_samples(512, sample_rate);
+    let expected = plain.process_clip(8, 3.0, &in_b);
+
+    for floor in [1e-9, 0.0] {
+        let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+        shifter.set_magnitude_floor(floor);
+        shifter.set_expander(-40.0, 4.0, 6.0);
+        let out_b = shifter.process_clip(8, 3.0, &in_b);
+        assert!(out_b.iter().all(|s| s.is_finite()), "floor {}", floor);
+
+        // within a frame and a few hops of the onset
+        let loud = 5000..7500;
+        let ratio = rms(&out_b[loud.clone()]) / rms(&expected[loud]);
+        assert!((ratio - 1.0).abs() < 1e-3, "floor {}: loud part changed by {}", floor, ratio);
+    }
+}
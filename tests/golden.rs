@@ -0,0 +1,396 @@
+mod common;
+
+use common::{assert_buffers_close, crossing_frequency, sine, tone_level, UNITY_GAIN};
+use pitch_shift::PitchShifter;
+
+const SAMPLE_RATE: usize = 8000;
+
+fn shifter() -> PitchShifter {
+    PitchShifter::with_window_samples(512, SAMPLE_RATE)
+}
+
+#[test]
+fn unity_shift_is_a_scaled_copy() {
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let out_b = shifter().process_clip(8, 0.0, &in_b);
+
+    // the beginning & end hold partial frames
+    let steady = 1000..(SAMPLE_RATE - 1000);
+    let expected: Vec<f32> = in_b[steady.clone()].iter().map(|s| s * UNITY_GAIN).collect();
+    assert_buffers_close(&out_b[steady], &expected, 2e-3);
+}
+
+#[test]
+fn octave_up_doubles_frequency() {
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let out_b = shifter().process_clip(8, 12.0, &in_b);
+    let hz = crossing_frequency(&out_b[1000..7000], SAMPLE_RATE);
+    assert!((hz - 500.0).abs() < 2.0, "{}", hz);
+}
+
+#[test]
+fn octave_down_halves_frequency() {
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let out_b = shifter().process_clip(8, -12.0, &in_b);
+    let hz = crossing_frequency(&out_b[1000..7000], SAMPLE_RATE);
+    assert!((hz - 125.0).abs() < 2.0, "{}", hz);
+}
+
+#[test]
+fn silence_stays_silent() {
+    let in_b = vec![0.0; SAMPLE_RATE];
+    for shift in [-12.0, 0.0, 7.0] {
+        let out_b = shifter().process_clip(8, shift, &in_b);
+        assert_buffers_close(&out_b, &in_b, 0.0);
+    }
+}
+
+#[test]
+fn silence_stays_silent_with_any_settings() {
+    use pitch_shift::{BinMapping, PhaseMode};
+
+    let settings: [fn(&mut PitchShifter); 10] = [
+        |s| s.set_highpass_hz(20.0),
+        |s| s.set_emphasis(0.95),
+        |s| s.set_auto_gain_match(true),
+        |s| s.set_frame_gain_normalization(true),
+        |s| s.set_energy_preservation(true),
+        |s| s.set_harmonic_excite(0.5),
+        |s| s.set_expander(0.01, 3.0, 6.0),
+        |s| s.set_bin_mapping(BinMapping::Interpolate),
+        |s| s.set_phase_mode(PhaseMode::HarmonicLocked),
+        |s| s.set_phase_dispersion(1.0),
+    ];
+
+    let tone = sine(440.0, SAMPLE_RATE, SAMPLE_RATE);
+    let in_b = vec![0.0; SAMPLE_RATE];
+    for setting in settings {
+        for shift in [-12.0, 0.0, 7.0] {
+            let mut shifter = shifter();
+            setting(&mut shifter);
+            let mut out_b = vec![1.0; in_b.len()];
+
+            // including the latency, in blocks, and after a reset
+            for (in_c, out_c) in in_b.chunks(100).zip(out_b.chunks_mut(100)) {
+                shifter.shift_pitch(8, shift, in_c, out_c);
+            }
+            assert_buffers_close(&out_b, &in_b, 0.0);
+
+            let mut tone_b = vec![0.0; tone.len()];
+            shifter.shift_pitch(8, shift, &tone, &mut tone_b);
+            shifter.reset();
+            shifter.shift_pitch(8, shift, &in_b, &mut out_b);
+            assert_buffers_close(&out_b, &in_b, 0.0);
+        }
+    }
+}
+
+#[test]
+fn bypass_on_unity_sweeps_through_zero_smoothly() {
+    let in_b = sine(440.0, 2 * SAMPLE_RATE, SAMPLE_RATE);
+    let render = |bypass| {
+        let mut shifter = PitchShifter::with_window_samples(256, SAMPLE_RATE);
+        shifter.set_bypass_on_unity(bypass);
+        let mut out_b = vec![0.0; in_b.len()];
+        for (i, (in_c, out_c)) in in_b.chunks(64).zip(out_b.chunks_mut(64)).enumerate() {
+            // from -1 to 1 semitone, holding 0 in the middle third
+            let shift = match i * 3 / 250 {
+                0 => i as f32 / 83.0 - 1.0,
+                1 => 0.0,
+                _ => (i - 166) as f32 / 83.0,
+            };
+            shifter.shift_pitch(8, shift, in_c, out_c);
+        }
+        out_b
+    };
+
+    // a click would be a step larger than the steepest slope of the tone
+    let largest_step = |out_b: Vec<f32>| {
+        let steps = out_b[256..].windows(2).map(|w| (w[1] - w[0]).abs());
+        steps.fold(0.0, f32::max)
+    };
+    let processed = largest_step(render(false));
+    let bypassed = largest_step(render(true));
+    assert!(bypassed <= processed * 1.05, "largest step is {} with bypass, {} without", bypassed, processed);
+}
+
+#[test]
+fn retune_isnt_bypassed() {
+    let in_b = sine(440.0, SAMPLE_RATE, SAMPLE_RATE);
+    let mut shifter = shifter();
+    shifter.set_bypass_on_unity(true);
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter.retune_to_hz(8, 330.0, &in_b, &mut out_b);
+
+    let hz = crossing_frequency(&out_b[2048..], SAMPLE_RATE);
+    assert!((hz - 330.0).abs() < 2.0, "retuned to {} Hz", hz);
+}
+
+#[test]
+fn fine_tune_keeps_small_frames_in_tune() {
+    // with 12 samples frames, partials collide in 7 bins
+    let mut shifter = PitchShifter::with_window_samples(12, SAMPLE_RATE);
+    shifter.set_shift(5.0);
+    shifter.set_over_sampling(4);
+    // `measure_shift` shifts a 440 Hz sine
+    let error_hz = |semitones: f32| 440.0 * (2.0f32.powf(semitones / 12.0) - 2.0f32.powf(5.0 / 12.0));
+
+    let untuned = error_hz(shifter.measure_shift(4, 5.0));
+    shifter.set_fine_tune(true);
+    let tuned = error_hz(shifter.measure_shift(4, 5.0));
+    assert!(untuned.abs() > 0.2, "off by {} Hz without fine tune", untuned);
+    assert!(tuned.abs() < 0.01, "off by {} Hz with fine tune", tuned);
+}
+
+#[test]
+fn fine_tune_follows_quality_presets() {
+    use pitch_shift::Quality;
+
+    let error_hz = |semitones: f32| 440.0 * (2.0f32.powf(semitones / 12.0) - 2.0f32.powf(5.0 / 12.0));
+    let mut untuned = PitchShifter::with_window_samples(12, SAMPLE_RATE);
+    untuned.set_shift(5.0);
+    untuned.set_quality(Quality::Normal);
+    let untuned = error_hz(untuned.measure_shift(8, 5.0));
+
+    // tuned for 4 times over-sampling, then switched to 8
+    let mut shifter = PitchShifter::with_window_samples(12, SAMPLE_RATE);
+    shifter.set_shift(5.0);
+    shifter.set_over_sampling(4);
+    shifter.set_fine_tune(true);
+    shifter.set_quality(Quality::Normal);
+    let tuned = error_hz(shifter.measure_shift(8, 5.0));
+    assert!(tuned.abs() < untuned.abs() / 10.0, "off by {} Hz, {} Hz without fine tune", tuned, untuned);
+}
+
+#[test]
+fn band_shift_leaves_other_partials() {
+    let sample_rate = 16000;
+    let low = sine(300.0, sample_rate, sample_rate);
+    let high = sine(2000.0, sample_rate, sample_rate);
+    let in_b: Vec<f32> = low.iter().zip(&high).map(|(l, h)| 0.5 * (l + h)).collect();
+
+    let mut shifter = PitchShifter::with_window_samples(1024, sample_rate);
+    let out_b = shifter.process_clip(8, 12.0, &in_b);
+    let plain = |hz| tone_level(&out_b[4000..12000], hz, sample_rate);
+    assert!(plain(300.0) < 0.01 && plain(600.0) > 0.2);
+
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter.shift_pitch_band(8, 12.0, (1000.0, 3000.0), &in_b, &mut out_b);
+    let level = |hz| tone_level(&out_b[4000..12000], hz, sample_rate);
+    assert!((level(300.0) - 0.5 * UNITY_GAIN).abs() < 0.01, "{}", level(300.0));
+    assert!(level(2000.0) < 0.01 && level(4000.0) > 0.2, "{} {}", level(2000.0), level(4000.0));
+}
+
+#[test]
+fn linear_shift_translates_partials() {
+    let fundamental = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let harmonic = sine(500.0, SAMPLE_RATE, SAMPLE_RATE);
+    let in_b: Vec<f32> = fundamental.iter().zip(&harmonic).map(|(f, h)| 0.5 * (f + h)).collect();
+
+    // 6.4 bins of 15.625 Hz: both partials move up by 100 Hz
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter().shift_pitch_linear(8, 6.4, &in_b, &mut out_b);
+    let level = |hz| tone_level(&out_b[2000..7000], hz, SAMPLE_RATE);
+    for hz in [350.0, 600.0] {
+        assert!((level(hz) - 0.5 * UNITY_GAIN).abs() < 0.03, "{} Hz: {}", hz, level(hz));
+    }
+    assert!(level(250.0) < 0.01 && level(700.0) < 0.01, "{} {}", level(250.0), level(700.0));
+}
+
+#[test]
+fn frequency_shift_adds_hz() {
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter().frequency_shift(8, 130.0, &in_b, &mut out_b);
+    let hz = crossing_frequency(&out_b[2000..7000], SAMPLE_RATE);
+    assert!((hz - 380.0).abs() < 2.0, "{}", hz);
+
+    // shifted below 0 Hz
+    shifter().frequency_shift(8, -300.0, &in_b, &mut out_b);
+    assert!(out_b[2000..7000].iter().all(|s| s.abs() < 0.01));
+}
+
+#[test]
+fn split_outputs_are_aligned() {
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let mut wet = vec![0.0; in_b.len()];
+    let mut dry = vec![0.0; in_b.len()];
+
+    let mut shifter = shifter();
+    let chunks = in_b.chunks(100).zip(wet.chunks_mut(100).zip(dry.chunks_mut(100)));
+    for (in_c, (wet_c, dry_c)) in chunks {
+        shifter.shift_pitch_split(8, 0.0, in_c, wet_c, dry_c);
+    }
+
+    let latency = shifter.latency_samples(8);
+    assert!(dry[..latency].iter().all(|s| *s == 0.0));
+    assert_eq!(dry[latency..], in_b[..in_b.len() - latency]);
+
+    let steady = 1000..in_b.len();
+    let expected: Vec<f32> = dry[steady.clone()].iter().map(|s| s * UNITY_GAIN).collect();
+    assert_buffers_close(&wet[steady], &expected, 2e-3);
+}
+
+#[test]
+fn recommended_over_sampling_grows_with_shift() {
+    let sample_rate = 16000;
+    let low = sine(220.0, sample_rate, sample_rate);
+    let high = sine(663.0, sample_rate, sample_rate);
+    let in_b: Vec<f32> = low.iter().zip(&high).map(|(l, h)| 0.5 * (l + h)).collect();
+
+    let mut shifter = PitchShifter::with_window_samples(1024, sample_rate);
+    let small = shifter.recommend_over_sampling(3.0, &in_b, -35.0);
+    let octave = shifter.recommend_over_sampling(12.0, &in_b, -35.0);
+    assert_eq!(small, 4);
+    assert!(octave >= 8, "{}", octave);
+    assert_eq!(shifter.recommend_over_sampling(0.0, &vec![0.0; 4096], -35.0), 1);
+}
+
+#[test]
+fn low_latency_halves_the_delay() {
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let mut shifter = shifter();
+    shifter.set_low_latency(true);
+    assert_eq!((shifter.frame_size(), shifter.fft_size()), (256, 512));
+    assert_eq!(shifter.latency_samples(8), 256);
+    assert_eq!(shifter.config().frame_size, 512);
+
+    let out_b = shifter.process_clip(8, 0.0, &in_b);
+    let steady = 1000..(SAMPLE_RATE - 1000);
+    let expected: Vec<f32> = in_b[steady.clone()].iter().map(|s| s * UNITY_GAIN).collect();
+    assert_buffers_close(&out_b[steady], &expected, 2e-3);
+
+    let out_b = shifter.process_clip(8, 12.0, &in_b);
+    let hz = crossing_frequency(&out_b[1000..7000], SAMPLE_RATE);
+    assert!((hz - 500.0).abs() < 2.0, "{}", hz);
+
+    shifter.set_low_latency(false);
+    assert_eq!((shifter.frame_size(), shifter.fft_size()), (512, 512));
+}
+
+#[test]
+fn kaiser_window_keeps_unity_level() {
+    use pitch_shift::WindowFunction;
+
+    assert!(WindowFunction::Kaiser(0.0).coefficients(64).iter().all(|c| (c - 1.0).abs() < 1e-6));
+    let window = WindowFunction::Kaiser(8.6).coefficients(64);
+    assert!((window[32] - 1.0).abs() < 1e-6 && window[0] < 0.01);
+
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let mut shifter = shifter();
+    shifter.set_analysis_window(WindowFunction::Kaiser(8.6));
+    shifter.set_synthesis_window(WindowFunction::Kaiser(8.6));
+    let out_b = shifter.process_clip(16, 0.0, &in_b);
+    let level = tone_level(&out_b[1000..7000], 250.0, SAMPLE_RATE);
+    assert!((level - UNITY_GAIN).abs() < 0.01, "{}", level);
+}
+
+#[test]
+fn frame_gain_holds_through_silent_output() {
+    // the expander mutes the quiet first half entirely
+    let tone = sine(500.0, SAMPLE_RATE, SAMPLE_RATE);
+    let half = SAMPLE_RATE / 2;
+    let in_b: Vec<f32> = tone.iter().enumerate().map(|(i, s)| match i < half {
+        true => 1e-4 * s,
+        false => 0.5 * s,
+    }).collect();
+
+    let mut shifter = shifter();
+    shifter.set_expander(-40.0, 10.0, 0.0);
+    shifter.set_frame_gain_normalization(true);
+    let out_b = shifter.process_clip(8, 3.0, &in_b);
+    assert!(out_b[1000..half - 1000].iter().all(|s| s.abs() < 1e-12));
+
+    // the gain was held rather than pushed up to its maximum, so
+    // the onset, still expanded, doesn't overshoot the steady level
+    let rms = |c: &[f32]| (c.iter().map(|s| s * s).sum::<f32>() / c.len() as f32).sqrt();
+    let steady = rms(&out_b[(half + 2048)..(half + 3072)]);
+    let onset = out_b[(half - 256)..(half + 768)].chunks(64).map(rms).fold(0.0, f32::max);
+    assert!(onset < 1.05 * steady, "onset at {}, steady at {}", onset, steady);
+}
+
+#[test]
+fn zero_windows_give_silence() {
+    use pitch_shift::WindowFunction;
+
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    for bypass_on_unity in [false, true] {
+        let mut shifter = shifter();
+        shifter.set_synthesis_window(WindowFunction::Custom(vec![0.0; 512]));
+        shifter.set_bypass_on_unity(bypass_on_unity);
+        for shift in [0.0, 3.0] {
+            let out_b = shifter.process_clip(8, shift, &in_b);
+            assert!(out_b.iter().all(|s| *s == 0.0), "shift {}, bypass {}", shift, bypass_on_unity);
+        }
+    }
+}
+
+#[test]
+fn frame_gain_normalization_restores_lost_energy() {
+    let low = sine(500.0, SAMPLE_RATE, SAMPLE_RATE);
+    let high = sine(3000.0, SAMPLE_RATE, SAMPLE_RATE);
+    let in_b: Vec<f32> = low.iter().zip(&high).map(|(l, h)| 0.5 * (l + h)).collect();
+    let rms = |out_b: &[f32]| (out_b[2000..6000].iter().map(|s| s * s).sum::<f32>() / 4000.0).sqrt();
+    let expected = rms(&in_b) * UNITY_GAIN;
+
+    // the upper partial is shifted past Nyquist
+    let mut shifter = shifter();
+    let plain = rms(&shifter.process_clip(8, 12.0, &in_b));
+    shifter.set_frame_gain_normalization(true);
+    let normalized = rms(&shifter.process_clip(8, 12.0, &in_b));
+    // the overlap-add of shifted frames still loses a little
+    let (plain_error, error) = ((plain - expected).abs(), (normalized - expected).abs());
+    assert!(error < plain_error / 2.0, "{} vs {} ({} without)", normalized, expected, plain);
+
+    // unshifted frames are barely touched
+    let unity = shifter.process_clip(8, 0.0, &in_b);
+    let expected: Vec<f32> = in_b[1000..7000].iter().map(|s| s * UNITY_GAIN).collect();
+    assert_buffers_close(&unity[1000..7000], &expected, 0.01);
+}
+
+#[test]
+fn clipped_samples_are_counted_per_call() {
+    let loud: Vec<f32> = sine(250.0, SAMPLE_RATE, SAMPLE_RATE).iter().map(|s| 2.0 * s).collect();
+    let mut shifter = shifter();
+    let mut out_b = vec![0.0; loud.len()];
+    shifter.shift_pitch(8, 3.0, &loud, &mut out_b);
+    let clipped = out_b.iter().filter(|s| s.abs() > 1.0).count();
+    assert!(clipped > 0);
+    assert!(shifter.last_output_clipped());
+    assert_eq!(shifter.last_output_clip_count(), clipped);
+
+    shifter.set_clip_ceiling(1.5);
+    shifter.shift_pitch(8, 3.0, &loud, &mut out_b);
+    let clipped = out_b.iter().filter(|s| s.abs() > 1.5).count();
+    assert_eq!(shifter.last_output_clip_count(), clipped);
+
+    // the count starts over with each call
+    shifter.set_clip_ceiling(1.0);
+    shifter.reset();
+    shifter.shift_pitch(8, 3.0, &vec![0.0; loud.len()], &mut out_b);
+    assert!(!shifter.last_output_clipped());
+
+    // the limiter keeps samples within the ceiling
+    shifter.set_output_limiter(1.0);
+    shifter.shift_pitch(8, 3.0, &loud, &mut out_b);
+    assert_eq!(shifter.last_output_clip_count(), 0);
+}
+
+#[test]
+fn reverse_processing_stays_aligned() {
+    // silence, then a tone
+    let mut in_b = vec![0.0; SAMPLE_RATE / 2];
+    in_b.extend(sine(250.0, SAMPLE_RATE / 2, SAMPLE_RATE));
+
+    let mut shifter = shifter();
+    shifter.set_reverse_processing(true);
+    let out_b = shifter.process_clip(8, 12.0, &in_b);
+    assert_eq!(out_b.len(), in_b.len());
+
+    // nothing leaks further than a frame before the onset
+    let onset = SAMPLE_RATE / 2;
+    assert!(out_b[..(onset - 512)].iter().all(|s| s.abs() < 1e-4));
+    let hz = crossing_frequency(&out_b[(onset + 1000)..(SAMPLE_RATE - 1000)], SAMPLE_RATE);
+    assert!((hz - 500.0).abs() < 5.0, "{} Hz", hz);
+}
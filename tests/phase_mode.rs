@@ -0,0 +1,121 @@
+mod common;
+
+use common::{sine, tone_level};
+use pitch_shift::{PhaseMode, PitchShifter};
+
+use std::f64::consts::TAU;
+
+const SAMPLE_RATE: usize = 16000;
+const FRAME_SIZE: usize = 1024;
+const BLOCK: usize = 400;
+
+/// Phase of the fundamental of a 220 Hz tone with a 5 Hz vibrato
+/// of about 20 cents, at `t` seconds
+fn vibrato_phase(t: f64) -> f64 {
+    TAU * 220.0 * t - (220.0 * 0.018 / 5.0) * (TAU * 5.0 * t).cos()
+}
+
+/// Mean, over harmonics 2 to 4, of how steady the phase of each
+/// one stays relative to the fundamental's in `out_b`, from block
+/// to block: `1.0` when it's constant
+fn harmonic_coherence(out_b: &[f32], ratio: f64) -> f64 {
+    // phase of harmonic `h` over the block at `start`, relative to
+    // an ideal shift of the input
+    let phase = |start: usize, h: f64| {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, sample) in out_b.iter().enumerate().skip(start).take(BLOCK) {
+            let t = i as f64 / SAMPLE_RATE as f64;
+            let (sin, cos) = (h * ratio * vibrato_phase(t)).sin_cos();
+            re += *sample as f64 * cos;
+            im += *sample as f64 * sin;
+        }
+        im.atan2(re)
+    };
+
+    let starts = (4000..(out_b.len() - 2000)).step_by(BLOCK);
+    let coherence = |h: f64| {
+        let (mut re, mut im, mut blocks) = (0.0, 0.0, 0.0);
+        for start in starts.clone() {
+            let relative = phase(start, h) - h * phase(start, 1.0);
+            re += relative.cos();
+            im += relative.sin();
+            blocks += 1.0;
+        }
+        (re * re + im * im).sqrt() / blocks
+    };
+
+    (coherence(2.0) + coherence(3.0) + coherence(4.0)) / 3.0
+}
+
+/// One second of a sawtooth-like tone following [`vibrato_phase`]
+fn vibrato_tone() -> Vec<f32> {
+    (0..SAMPLE_RATE)
+        .map(|i| {
+            let phase = vibrato_phase(i as f64 / SAMPLE_RATE as f64);
+            let partials = (1..=8).map(|h| (h as f64 * phase).sin() / h as f64);
+            0.3 * partials.sum::<f64>() as f32
+        })
+        .collect()
+}
+
+#[test]
+fn harmonic_locking_keeps_harmonics_coherent() {
+    let in_b = vibrato_tone();
+
+    for shift in [3.0, -5.0] {
+        let ratio = 2.0_f64.powf(shift as f64 / 12.0);
+        let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+        let standard = harmonic_coherence(&shifter.process_clip(4, shift, &in_b), ratio);
+        shifter.set_phase_mode(PhaseMode::HarmonicLocked);
+        let locked = harmonic_coherence(&shifter.process_clip(4, shift, &in_b), ratio);
+        assert!(locked > standard + 0.05, "shift {}: {} locked vs {}", shift, locked, standard);
+    }
+}
+
+#[test]
+fn phase_coherence_reflects_locking() {
+    let in_b = vibrato_tone();
+    // mean over frames, once the first one is complete
+    let mean_coherence = |shifter: &mut PitchShifter| {
+        let mut out_b = [0.0; 256];
+        let hops = in_b.chunks_exact(256).map(|hop| {
+            shifter.shift_pitch(4, 3.0, hop, &mut out_b);
+            shifter.last_phase_coherence()
+        });
+        let coherences: Vec<f32> = hops.skip(8).collect();
+        coherences.iter().sum::<f32>() / coherences.len() as f32
+    };
+
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    let standard = mean_coherence(&mut shifter);
+    shifter.reset();
+    shifter.set_phase_mode(PhaseMode::HarmonicLocked);
+    let locked = mean_coherence(&mut shifter);
+    assert!(standard < 0.97, "{}", standard);
+    assert!(locked > 0.99, "{}", locked);
+}
+
+#[test]
+fn phase_dispersion_turns_tones_into_noise() {
+    let in_b = sine(250.0, SAMPLE_RATE / 2, SAMPLE_RATE);
+    let shifted_hz = 250.0 * 2.0_f32.powf(3.0 / 12.0);
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, SAMPLE_RATE);
+    let level = |shifter: &mut PitchShifter| {
+        let out_b = shifter.process_clip(8, 3.0, &in_b);
+        tone_level(&out_b[2000..6000], shifted_hz, SAMPLE_RATE)
+    };
+
+    let plain = level(&mut shifter);
+    shifter.set_phase_dispersion(0.1);
+    let rng = shifter.rng().clone();
+    let subtle = level(&mut shifter);
+    shifter.set_phase_dispersion(1.0);
+    let random = level(&mut shifter);
+    assert!(subtle > plain * 0.97, "{} vs {}", subtle, plain);
+    assert!(random < plain * 0.5, "{} vs {}", random, plain);
+
+    // the same random sequence gives the same output
+    shifter.set_phase_dispersion(0.1);
+    shifter.set_rng(rng);
+    assert_eq!(level(&mut shifter), subtle);
+}
@@ -0,0 +1,133 @@
+use pitch_shift::{Complex, FftBackend, FftNormalization, PitchShifter, Rng, SynthesisScaling, WindowFunction};
+
+use std::f32::consts::TAU;
+
+#[test]
+fn wide_accumulator_only_changes_rounding() {
+    let sample_rate = 8000;
+    let len = sample_rate * 10;
+    let in_b: Vec<f32> = (0..len)
+        .map(|i| (TAU * 500.0 * i as f32 / sample_rate as f32).sin())
+        .collect();
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    let narrow = shifter.process_clip(8, 3.0, &in_b);
+    shifter.set_wide_accumulator(true);
+    let wide = shifter.process_clip(8, 3.0, &in_b);
+
+    assert_ne!(narrow, wide);
+    for (n, w) in narrow.iter().zip(&wide) {
+        assert!((n - w).abs() < 1e-5, "{n} vs {w}");
+    }
+
+    // and switching back restores the default path exactly
+    shifter.set_wide_accumulator(false);
+    assert_eq!(narrow, shifter.process_clip(8, 3.0, &in_b));
+}
+
+/// Resynthesizes the same frame whatever the analysis, so the
+/// output is an overlap-add whose exact value is known
+struct FixedFrame(Vec<f32>);
+
+impl FftBackend for FixedFrame {
+    fn scratch_len(&mut self, _fft_size: usize) -> usize {
+        0
+    }
+
+    fn forward(&mut self, _input: &mut [f32], output: &mut [Complex<f32>], _scratch: &mut [Complex<f32>]) {
+        output.fill(Complex::new(0.0, 0.0));
+    }
+
+    fn inverse(&mut self, _input: &mut [Complex<f32>], output: &mut [f32], _scratch: &mut [Complex<f32>]) {
+        output.copy_from_slice(&self.0);
+    }
+
+    fn normalization(&self, _fft_size: usize) -> FftNormalization {
+        FftNormalization::UNNORMALIZED
+    }
+}
+
+#[test]
+fn wide_accumulator_is_closer_to_an_f64_overlap_add() {
+    let (frame_size, over_sampling) = (512, 32);
+    let step = frame_size / over_sampling;
+    let mut rng = Rng::from_seed(7);
+    // spanning a few decades, so that partial sums round a lot
+    let frame: Vec<f32> = (0..frame_size)
+        .map(|_| (rng.next_f32() - 0.5) * 10f32.powf(6.0 * rng.next_f32() - 3.0))
+        .collect();
+    let window = WindowFunction::Hann.coefficients(frame_size);
+    let scaling = SynthesisScaling {
+        transform: 1.0,
+        overlap: 1.0 / over_sampling as f32,
+        window: 1.0,
+        gain: 1.0,
+    };
+
+    // each output sample sums one product of every overlapping frame
+    let reference: Vec<f64> = (0..step)
+        .map(|i| {
+            let products = (i..frame_size).step_by(step).map(|k| window[k] as f64 * frame[k] as f64);
+            products.sum::<f64>() * scaling.total() as f64
+        })
+        .collect();
+
+    let sample_rate = 8000;
+    let in_b: Vec<f32> = (0..sample_rate * 10).map(|i| (i as f32 * 0.1).sin()).collect();
+    let mut errors = [0.0; 2];
+    for (wide, error) in [false, true].into_iter().zip(&mut errors) {
+        let mut shifter = PitchShifter::with_window_samples(frame_size, sample_rate);
+        shifter.set_fft_backend(FixedFrame(frame.clone()));
+        shifter.set_synthesis_scaling(Some(scaling));
+        shifter.set_wide_accumulator(wide);
+        let mut out_b = vec![0.0; in_b.len()];
+        shifter.shift_pitch(over_sampling, 0.0, &in_b, &mut out_b);
+
+        // past the ramp up, frames overlap fully and their output
+        // repeats every hop
+        let steady = &out_b[2 * frame_size..];
+        let phase = (0..step)
+            .min_by_key(|phase| ((steady[0] as f64 - reference[*phase]).abs() * 1e12) as u64)
+            .unwrap();
+        *error = steady
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (*s as f64 - reference[(phase + i) % step]).powi(2))
+            .sum::<f64>();
+    }
+
+    let [narrow, wide] = errors;
+    assert!(2.0 * wide < narrow, "wide accumulator error {wide} vs {narrow}");
+}
+
+#[test]
+fn output_scales_with_input() {
+    let sample_rate = 8000;
+    let in_b: Vec<f32> = (0..sample_rate)
+        .map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            (TAU * 300.0 * t).sin() + 0.3 * (TAU * 1100.0 * t).sin()
+        })
+        .collect();
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    let reference = shifter.process_clip(8, 5.0, &in_b);
+
+    // powers of two only change exponents, e.g. raw i16 magnitudes
+    for scale in [32768.0, 1.0 / 1024.0] {
+        let scaled: Vec<f32> = in_b.iter().map(|s| s * scale).collect();
+        let expected: Vec<f32> = reference.iter().map(|s| s * scale).collect();
+        assert_eq!(shifter.process_clip(8, 5.0, &scaled), expected);
+    }
+
+    for scale in [3.0, 1e-3] {
+        let scaled: Vec<f32> = in_b.iter().map(|s| s * scale).collect();
+        let output = shifter.process_clip(8, 5.0, &scaled);
+        // other scales round differently, which the phases of the
+        // fading partials of the last frame are sensitive to
+        let steady = ..(output.len() - 512);
+        for (o, r) in output[steady].iter().map(|o| o / scale).zip(&reference[steady]) {
+            assert!((o - r).abs() < 1e-4, "{o} vs {r}, scale {scale}");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use pitch_shift::PitchShifter;
+
+use std::f32::consts::TAU;
+
+fn two_tones(len: usize, sample_rate: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            (TAU * 440.0 * t).sin() + 0.5 * (TAU * 1250.0 * t).sin()
+        })
+        .collect()
+}
+
+#[cfg(feature = "realfft")]
+fn assert_close(actual: f32, expected: f32) {
+    let tolerance = 1e-4 * expected.abs().max(1.0);
+    assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+}
+
+// reference values produced before `synthesized_*` buffers were right-sized,
+// with unpadded 400 samples FFTs, which the builtin backend pads
+#[cfg(feature = "realfft")]
+#[test]
+fn output_matches_reference() {
+    let sample_rate = 8000;
+    let in_b = two_tones(4000, sample_rate);
+    let mut out_b = vec![0.0; in_b.len()];
+
+    let mut shifter = PitchShifter::new(50, sample_rate);
+    shifter.shift_pitch(8, 5.0, &in_b, &mut out_b);
+
+    assert_close(out_b[1000], 0.37366903);
+    assert_close(out_b[2000], -0.28193438);
+    assert_close(out_b[3999], 0.42374808);
+    assert_close(out_b.iter().map(|s| s * s).sum(), 589.97614);
+}
+
+#[test]
+fn window_tables_match_window_setters() {
+    use pitch_shift::{WindowFunction, WindowTables};
+
+    let sample_rate = 8000;
+    let in_b = two_tones(4000, sample_rate);
+
+    let mut expected = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(400, sample_rate);
+    shifter.set_analysis_window(WindowFunction::Blackman);
+    shifter.set_synthesis_window(WindowFunction::Hamming);
+    shifter.shift_pitch(8, 5.0, &in_b, &mut expected);
+
+    let tables = WindowTables::new(400, WindowFunction::Blackman, WindowFunction::Hamming);
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_tables(&tables, sample_rate);
+    shifter.shift_pitch(8, 5.0, &in_b, &mut out_b);
+    assert_eq!(out_b, expected);
+
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_tables(&shifter.window_tables(), sample_rate);
+    shifter.shift_pitch(8, 5.0, &in_b, &mut out_b);
+    assert_eq!(out_b, expected);
+}
+
+#[test]
+fn resized_window_matches_new_shifter() {
+    let sample_rate = 8000;
+    let in_b = two_tones(4000, sample_rate);
+
+    let mut shifter = PitchShifter::new(50, sample_rate);
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter.shift_pitch(8, 3.0, &in_b, &mut out_b);
+
+    for (window_duration_ms, zero_padding) in [(30, false), (75, false), (30, true)] {
+        let mut expected = vec![0.0; in_b.len()];
+        let mut fresh = PitchShifter::new(window_duration_ms, sample_rate);
+        fresh.set_zero_padding(zero_padding);
+        fresh.shift_pitch(8, 3.0, &in_b, &mut expected);
+
+        shifter.set_zero_padding(zero_padding);
+        shifter.resize_window(window_duration_ms);
+        assert_eq!(shifter.frame_size(), fresh.frame_size());
+        shifter.shift_pitch(8, 3.0, &in_b, &mut out_b);
+        assert_eq!(out_b, expected);
+    }
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{frequency_response, log_sweep, sine, tone_level, UNITY_GAIN};
+use pitch_shift::PitchShifter;
+
+const SAMPLE_RATE: usize = 8000;
+
+#[test]
+fn unity_shift_has_a_flat_response() {
+    let (in_b, hz) = log_sweep(40.0, 3800.0, 8 * SAMPLE_RATE, SAMPLE_RATE);
+    let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+    let out_b = shifter.process_clip(8, 0.0, &in_b);
+
+    // a window or normalization bug would tilt the response
+    let unity_db = 20.0 * UNITY_GAIN.log10();
+    let response: Vec<(f32, f32)> = frequency_response(&in_b, &out_b, &hz, 1000)
+        .into_iter()
+        .map(|(hz, db)| (hz, db - unity_db))
+        .collect();
+    let worst = response.iter().map(|(_, db)| db.abs()).fold(0.0, f32::max);
+    assert!(worst < 0.1, "response deviates by {} dB: {:?}", worst, response);
+}
+
+#[test]
+fn emphasis_keeps_the_spectral_balance() {
+    let (low_hz, high_hz) = (150.0, 1500.0);
+    let in_b: Vec<f32> = sine(low_hz, 4 * SAMPLE_RATE, SAMPLE_RATE)
+        .iter()
+        .zip(sine(high_hz, 4 * SAMPLE_RATE, SAMPLE_RATE))
+        .map(|(low, high)| 0.5 * low + 0.05 * high)
+        .collect();
+
+    // de-emphasized at the analyzed frequencies, the moved partials
+    // keep the levels they'd have without emphasis
+    let ratio = 2.0f32.powf(7.0 / 12.0);
+    let levels = |coeff| {
+        let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+        shifter.set_emphasis(coeff);
+        let out_b = shifter.process_clip(8, 7.0, &in_b);
+        let steady = &out_b[SAMPLE_RATE..];
+        [low_hz, high_hz].map(|hz| 20.0 * tone_level(steady, hz * ratio, SAMPLE_RATE).log10())
+    };
+
+    let plain = levels(0.0);
+    let emphasized = levels(0.95);
+    for (plain, emphasized) in plain.iter().zip(&emphasized) {
+        assert!((plain - emphasized).abs() < 0.5, "{:?} dB without emphasis, {:?} dB with", plain, emphasized);
+    }
+}
@@ -0,0 +1,73 @@
+use pitch_shift::{PitchShifter, SynthesisScaling};
+
+const FRAME_SIZE: usize = 512;
+const OVER_SAMPLING: usize = 8;
+const BINS: usize = FRAME_SIZE / 2 + 1;
+
+/// Peak amplitude of the steady output of a single bin at its
+/// center frequency
+fn single_bin_amplitude(shifter: &mut PitchShifter, magnitude: f32) -> f32 {
+    shifter.set_over_sampling(OVER_SAMPLING);
+    let mut magnitudes = vec![0.0; BINS];
+    let mut frequencies = vec![0.0; BINS];
+    magnitudes[32] = magnitude;
+    frequencies[32] = shifter.bin_frequency_hz(32);
+
+    let mut hop = vec![0.0; FRAME_SIZE / OVER_SAMPLING];
+    let mut peak: f32 = 0.0;
+    for frame in 0..(4 * OVER_SAMPLING) {
+        shifter.synthesize_frame(&magnitudes, &frequencies, &mut hop);
+        // skip the fade-in of the first frames
+        if frame >= OVER_SAMPLING {
+            peak = hop.iter().fold(peak, |peak, s| peak.max(s.abs()));
+        }
+    }
+    peak
+}
+
+#[test]
+fn single_bin_reconstructs_to_expected_amplitude() {
+    let mut shifter = PitchShifter::with_window_samples(FRAME_SIZE, 8000);
+    let scaling = shifter.synthesis_scaling(OVER_SAMPLING);
+
+    // the inverse real FFT turns the bin into a cosine of twice its
+    // magnitude, and Hann synthesis windows overlap-add to an
+    // average of `over_sampling / 2`
+    let expected = 100.0 * scaling.total() * OVER_SAMPLING as f32;
+    let amplitude = single_bin_amplitude(&mut shifter, 100.0);
+    assert!((amplitude - expected).abs() < 1e-4 * expected, "{} vs {}", amplitude, expected);
+    assert!((expected - 100.0 / BINS as f32).abs() < 1e-6);
+
+    shifter.reset();
+    shifter.set_synthesis_scaling(Some(SynthesisScaling { gain: 2.0, ..scaling }));
+    let amplitude = single_bin_amplitude(&mut shifter, 100.0);
+    assert!((amplitude - 2.0 * expected).abs() < 2e-4 * expected, "{} vs {}", amplitude, 2.0 * expected);
+}
+
+#[test]
+fn planar_frames_match_interleaved_frames() {
+    use pitch_shift::{complex_to_planar, Complex};
+
+    let mut interleaved = PitchShifter::with_window_samples(FRAME_SIZE, 8000);
+    let mut planar = PitchShifter::with_window_samples(FRAME_SIZE, 8000);
+    interleaved.set_over_sampling(OVER_SAMPLING);
+    planar.set_over_sampling(OVER_SAMPLING);
+
+    let mut out_spectrum = vec![Complex::new(0.0, 0.0); BINS];
+    let (mut in_re, mut in_im) = (vec![0.0; BINS], vec![0.0; BINS]);
+    let (mut out_re, mut out_im) = (vec![0.0; BINS], vec![0.0; BINS]);
+    let (mut expected_re, mut expected_im) = (vec![0.0; BINS], vec![0.0; BINS]);
+    for frame in 0..8 {
+        // decaying magnitudes, with phases advancing from frame to frame
+        let in_spectrum: Vec<Complex<f32>> = (0..BINS)
+            .map(|k| Complex::from_polar(1.0 / (1 + k) as f32, frame as f32 * (0.3 + k as f32)))
+            .collect();
+
+        interleaved.shift_frame(4.0, &in_spectrum, &mut out_spectrum);
+        complex_to_planar(&in_spectrum, &mut in_re, &mut in_im);
+        planar.shift_frame_planar(4.0, &in_re, &in_im, &mut out_re, &mut out_im);
+
+        complex_to_planar(&out_spectrum, &mut expected_re, &mut expected_im);
+        assert_eq!((&out_re, &out_im), (&expected_re, &expected_im));
+    }
+}
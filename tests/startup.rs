@@ -0,0 +1,48 @@
+mod common;
+
+use common::sine;
+use pitch_shift::PitchShifter;
+
+const SAMPLE_RATE: usize = 8000;
+
+/// Error of the loudest peak measured on the first frame, in Hz
+fn first_frame_error(hz: f32, phase_reset: bool) -> f32 {
+    let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+    shifter.set_first_frame_phase_reset(phase_reset);
+
+    // exactly one hop, i.e. one frame
+    let in_b = sine(hz, 64, SAMPLE_RATE);
+    let mut out_b = vec![0.0; in_b.len()];
+    shifter.shift_pitch(8, 0.0, &in_b, &mut out_b);
+
+    let loudest = shifter.last_peaks().max_by(|a, b| a.magnitude.total_cmp(&b.magnitude));
+    (loudest.unwrap().frequency - hz).abs()
+}
+
+#[test]
+fn first_frame_phase_reset_reduces_startup_error() {
+    let bin_width = SAMPLE_RATE as f32 / 512.0;
+    for hz in [333.3, 517.0, 1210.0] {
+        let default = first_frame_error(hz, false);
+        let reset = first_frame_error(hz, true);
+        assert!(reset < default, "{} Hz: {} vs {}", hz, reset, default);
+        assert!(reset <= bin_width, "{} Hz: {}", hz, reset);
+    }
+}
+
+#[test]
+fn clip_level_settles_after_a_hop() {
+    let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    for (hz, shift) in [(250.0, 5.0), (250.0, -7.0), (333.0, 5.0), (333.0, -7.0)] {
+        let in_b = sine(hz, SAMPLE_RATE, SAMPLE_RATE);
+        let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+        let out_b = shifter.process_clip(8, shift, &in_b);
+
+        // hops are 64 samples long
+        let steady = rms(&out_b[4000..6000]);
+        let first_hop = rms(&out_b[..64]) / steady;
+        let next_hops = rms(&out_b[64..512]) / steady;
+        assert!((first_hop - 1.0).abs() < 0.35, "{} Hz, {} semitones: first hop at {}", hz, shift, first_hop);
+        assert!((next_hops - 1.0).abs() < 0.05, "{} Hz, {} semitones: next hops at {}", hz, shift, next_hops);
+    }
+}
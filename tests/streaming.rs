@@ -0,0 +1,149 @@
+use pitch_shift::PitchShifter;
+
+use std::f32::consts::TAU;
+
+fn chirp(len: usize, sample_rate: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            (TAU * (220.0 + 200.0 * t) * t).sin()
+        })
+        .collect()
+}
+
+#[test]
+fn small_blocks_match_bulk_processing() {
+    let sample_rate = 44100;
+    let in_b = chirp(sample_rate / 2, sample_rate);
+
+    let mut bulk = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(2048, sample_rate);
+    shifter.shift_pitch(4, 3.0, &in_b, &mut bulk);
+
+    let mut blocks = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(2048, sample_rate);
+    for (in_block, out_block) in in_b.chunks(64).zip(blocks.chunks_mut(64)) {
+        shifter.shift_pitch(4, 3.0, in_block, out_block);
+    }
+
+    assert_eq!(bulk, blocks);
+}
+
+#[test]
+fn in_place_matches_separate_buffers() {
+    let sample_rate = 44100;
+    let in_b = chirp(sample_rate / 4, sample_rate);
+
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(2048, sample_rate);
+    shifter.shift_pitch(4, -5.0, &in_b, &mut out_b);
+
+    let mut buffer = in_b.clone();
+    let mut shifter = PitchShifter::with_window_samples(2048, sample_rate);
+    shifter.shift_pitch_in_place(4, -5.0, &mut buffer);
+
+    assert_eq!(out_b, buffer);
+}
+
+#[test]
+fn iterator_yields_flushed_tail() {
+    let sample_rate = 8000;
+    let in_b = chirp(3000, sample_rate);
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    let latency = shifter.latency_samples(8);
+    let collected: Vec<f32> = shifter.shift_iter(8, 4.0, in_b.iter().copied()).collect();
+    assert_eq!(collected.len(), in_b.len() + latency);
+
+    let mut shifter = PitchShifter::with_window_samples(512, sample_rate);
+    let clip = shifter.process_clip(8, 4.0, &in_b);
+    assert_eq!(&collected[latency..], &clip[..]);
+}
+
+#[test]
+fn phase_state_stitches_chunks() {
+    let sample_rate = 44100;
+    let (frame_size, over_sampling) = (2048, 4);
+    let in_b = chirp(sample_rate / 2, sample_rate);
+    // on a hop boundary
+    let seam = 512 * 20;
+
+    let mut whole = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(frame_size, sample_rate);
+    shifter.shift_pitch(over_sampling, 3.0, &in_b, &mut whole);
+
+    let mut first = PitchShifter::with_window_samples(frame_size, sample_rate);
+    let mut out_b = vec![0.0; seam];
+    first.shift_pitch(over_sampling, 3.0, &in_b[..seam], &mut out_b);
+
+    let mut second = PitchShifter::with_window_samples(frame_size, sample_rate);
+    let mut pre_roll = vec![0.0; frame_size];
+    second.shift_pitch(over_sampling, 3.0, &in_b[seam - frame_size..seam], &mut pre_roll);
+    second.set_phase_state(first.last_phase(), first.phase_sum()).unwrap();
+
+    let mut out_b = vec![0.0; in_b.len() - seam];
+    second.shift_pitch(over_sampling, 3.0, &in_b[seam..], &mut out_b);
+
+    assert_eq!(out_b[frame_size..], whole[seam + frame_size..]);
+}
+
+#[test]
+fn spectrogram_frames_come_with_the_audio() {
+    let sample_rate = 8000;
+    let in_b: Vec<f32> = (0..1000).map(|i| (TAU * 250.0 * i as f32 / sample_rate as f32).sin()).collect();
+
+    let mut expected = vec![0.0; in_b.len()];
+    let mut shifter = PitchShifter::with_window_samples(256, sample_rate);
+    shifter.shift_pitch(4, 3.0, &in_b, &mut expected);
+
+    let mut out_b = vec![0.0; in_b.len()];
+    let mut frames = Vec::new();
+    let mut shifter = PitchShifter::with_window_samples(256, sample_rate);
+    shifter.shift_pitch_with_spectrogram(4, 3.0, &in_b, &mut out_b, |magnitudes| frames.push(magnitudes.to_vec()));
+    assert_eq!(out_b, expected);
+
+    // a frame every 64 samples, the last ones peaking at 250 Hz
+    assert_eq!(frames.len(), in_b.len() / 64);
+    let last = frames.last().unwrap();
+    assert_eq!(last.len(), 129);
+    let peak = (0..last.len()).max_by(|a, b| last[*a].total_cmp(&last[*b])).unwrap();
+    assert_eq!(peak, 8);
+}
+
+#[test]
+fn streaming_shifter_matches_clip_processing() {
+    use pitch_shift::StreamingShifter;
+
+    let sample_rate = 44100;
+    let in_b = chirp(sample_rate / 4, sample_rate);
+    let mut shifter = PitchShifter::with_window_samples(2048, sample_rate);
+    shifter.set_over_sampling(4);
+    shifter.set_shift(-5.0);
+    let expected = shifter.process_clip(4, -5.0, &in_b);
+
+    let mut stream = StreamingShifter::new(shifter, 300);
+    let mut out_b = Vec::new();
+    let mut read = |stream: &mut StreamingShifter| {
+        while let Some(block) = stream.read() {
+            assert!(block.len() == 300 || out_b.len() + block.len() == in_b.len());
+            out_b.extend_from_slice(block);
+        }
+    };
+
+    // written in chunks of unrelated sizes
+    let mut rest = &in_b[..];
+    for size in [1, 700, 64, 2500].iter().cycle() {
+        let (chunk, next) = rest.split_at((*size).min(rest.len()));
+        stream.write(chunk);
+        read(&mut stream);
+        rest = next;
+        if rest.is_empty() {
+            break;
+        }
+    }
+    stream.finish();
+    stream.finish();
+    read(&mut stream);
+
+    assert_eq!(out_b, expected);
+}
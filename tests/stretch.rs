@@ -0,0 +1,47 @@
+mod common;
+
+use common::{crossing_frequency, sine};
+use pitch_shift::PitchShifter;
+
+const SAMPLE_RATE: usize = 8000;
+
+#[test]
+fn time_stretch_reports_written_samples() {
+    let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+    let in_b = sine(250.0, 1000, SAMPLE_RATE);
+    let mut out_b = vec![0.0; 4000];
+
+    // the last input sample waits for the next one
+    assert_eq!(shifter.time_stretch(8, 2.0, &in_b, &mut out_b), 1998);
+    assert_eq!(shifter.time_stretch(8, 2.0, &in_b, &mut out_b), 2000);
+
+    // partial fill: the rest stays queued
+    assert_eq!(shifter.time_stretch(8, 2.0, &in_b, &mut out_b[..500]), 500);
+    assert_eq!(shifter.time_stretch(8, 2.0, &[], &mut out_b), 1500);
+}
+
+#[test]
+fn time_stretch_keeps_pitch() {
+    let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+    let in_b = sine(250.0, SAMPLE_RATE, SAMPLE_RATE);
+    let mut out_b = vec![0.0; 2 * SAMPLE_RATE];
+
+    let written = shifter.time_stretch(8, 2.0, &in_b, &mut out_b);
+    let hz = crossing_frequency(&out_b[2000..written], SAMPLE_RATE);
+    assert!((hz - 250.0).abs() < 2.0, "{}", hz);
+}
+
+#[test]
+fn stretch_output_length_is_exact() {
+    let mut shifter = PitchShifter::with_window_samples(512, SAMPLE_RATE);
+    let in_b = sine(250.0, 1000, SAMPLE_RATE);
+    let mut out_b = vec![0.0; 4000];
+    let latency = shifter.latency_samples(8);
+    assert_eq!(shifter.output_length(in_b.len(), 8), in_b.len() + latency);
+
+    for ratio in [0.5, 0.7, 1.0, 1.3, 2.0, 3.0] {
+        shifter.reset();
+        let written: usize = in_b.chunks(97).map(|in_c| shifter.time_stretch(8, ratio, in_c, &mut out_b)).sum();
+        assert_eq!(written + latency, shifter.stretch_output_length(in_b.len(), ratio, 8), "ratio {}", ratio);
+    }
+}
@@ -0,0 +1,142 @@
+use pitch_shift::PitchShifter;
+
+#[test]
+#[should_panic(expected = "sample_rate must be greater than zero")]
+fn zero_sample_rate() {
+    PitchShifter::new(50, 0);
+}
+
+#[test]
+#[should_panic(expected = "window_duration_ms must be greater than zero")]
+fn zero_window_duration() {
+    PitchShifter::new(0, 44100);
+}
+
+#[test]
+#[should_panic(expected = "window contains no samples")]
+fn window_shorter_than_a_sample() {
+    PitchShifter::new(1, 100);
+}
+
+#[test]
+fn try_new_reports_errors() {
+    use pitch_shift::PitchShiftError;
+
+    assert_eq!(PitchShifter::try_new(50, 0).err(), Some(PitchShiftError::ZeroSampleRate));
+    assert_eq!(PitchShifter::try_new(0, 44100).err(), Some(PitchShiftError::ZeroWindowDuration));
+    let error = PitchShifter::try_new(1, 100).err();
+    assert_eq!(error, Some(PitchShiftError::EmptyWindow { sample_rate: 100 }));
+}
+
+#[test]
+fn try_shift_pitch_reports_errors() {
+    use pitch_shift::PitchShiftError;
+
+    let mut shifter = PitchShifter::with_window_samples(64, 8000);
+    let mut out_b = [0.0; 16];
+
+    let error = shifter.try_shift_pitch(0, 0.0, &[0.0; 16], &mut out_b);
+    assert_eq!(error, Err(PitchShiftError::InvalidOverSampling { over_sampling: 0, frame_size: 64 }));
+    let message = shifter.try_shift_pitch(128, 0.0, &[0.0; 16], &mut out_b).unwrap_err().to_string();
+    assert!(message.ends_with("got 128; these divide it exactly: 1, 2, 4, 8, 16, 32, 64"), "{}", message);
+    let error = shifter.try_shift_pitch(4, 0.0, &[0.0; 8], &mut out_b);
+    assert_eq!(error, Err(PitchShiftError::BufferTooShort { input_len: 8, output_len: 16 }));
+    assert_eq!(shifter.try_shift_pitch(4, 0.0, &[0.0; 16], &mut out_b), Ok(()));
+}
+
+#[test]
+fn overlap_percent_maps_to_over_sampling() {
+    use pitch_shift::PitchShiftError;
+
+    let mut shifter = PitchShifter::with_window_samples(512, 8000);
+    for (percent, over_sampling) in [(75.0, 4), (87.5, 8), (93.75, 16)] {
+        shifter.set_overlap_percent(percent).unwrap();
+        assert_eq!(shifter.over_sampling(), over_sampling);
+    }
+
+    let error = shifter.set_overlap_percent(80.0);
+    assert_eq!(error, Err(PitchShiftError::InexactOverlap {
+        percent: 80.0,
+        nearest_over_sampling: 4,
+        nearest_percent: 75.0,
+    }));
+    assert_eq!(shifter.over_sampling(), 16);
+    assert_eq!(shifter.set_overlap_percent(100.0), Err(PitchShiftError::InvalidOverlap { percent: 100.0 }));
+}
+
+#[test]
+#[should_panic(expected = "over_sampling must be between 1 and the frame size (64), got 65")]
+fn over_sampling_above_frame_size() {
+    let mut shifter = PitchShifter::with_window_samples(64, 8000);
+    let mut out_b = [0.0; 16];
+    shifter.shift_pitch(65, 0.0, &[0.0; 16], &mut out_b);
+}
+
+#[test]
+#[should_panic(expected = "over_sampling must be between 1 and the frame size (64), got 0")]
+fn flops_estimate_checks_over_sampling() {
+    PitchShifter::with_window_samples(64, 8000).estimated_flops_per_second(0);
+}
+
+#[test]
+fn shift_quantization_snaps_to_scale() {
+    use pitch_shift::QuantMode;
+
+    let mut shifter = PitchShifter::with_window_samples(512, 8000);
+    shifter.set_shift_quantization(QuantMode::Semitone);
+    assert_eq!(shifter.target_bin(100, 2.4), shifter.target_bin(100, 2.0));
+
+    // C major: 6 isn't a degree, 5 & 7 are
+    shifter.set_shift_quantization(QuantMode::Scale(vec![0, 2, 4, 5, 7, 9, 11]));
+    assert_eq!(shifter.target_bin(100, 6.3), shifter.target_bin(100, 7.0));
+    assert_eq!(shifter.target_bin(100, -6.3), shifter.target_bin(100, -7.0));
+    assert_eq!(shifter.target_bin(100, 5.6), shifter.target_bin(100, 5.0));
+}
+
+#[test]
+fn phase_state_length_is_checked() {
+    use pitch_shift::PitchShiftError;
+
+    let mut shifter = PitchShifter::with_window_samples(64, 8000);
+    assert_eq!(shifter.last_phase().len(), 33);
+    assert_eq!(shifter.phase_sum().len(), 33);
+
+    let bins = vec![0.5; 33];
+    assert_eq!(
+        shifter.set_phase_state(&bins, &bins[1..]),
+        Err(PitchShiftError::PhaseStateLength { expected: 33, last_phase_len: 33, phase_sum_len: 32 }),
+    );
+    assert_eq!(shifter.phase_sum(), &[0.0; 33]);
+
+    assert_eq!(shifter.set_phase_state(&bins, &bins), Ok(()));
+    assert_eq!(shifter.last_phase(), &bins[..]);
+}
+
+#[test]
+fn custom_windows_arent_resized() {
+    use pitch_shift::{PitchShiftError, WindowFunction};
+
+    let mut shifter = PitchShifter::with_window_samples(64, 8000);
+    let window = WindowFunction::Custom(WindowFunction::Hamming.coefficients(64));
+    shifter.set_analysis_window(window.clone());
+    let error = Err(PitchShiftError::CustomWindowResize { window_len: 64, frame_size: 32 });
+    assert_eq!(shifter.try_set_low_latency(true), error);
+    let error = Err(PitchShiftError::CustomWindowResize { window_len: 64, frame_size: 400 });
+    assert_eq!(shifter.try_resize_window(50), error);
+    assert_eq!(shifter.frame_size(), 64);
+    assert_eq!(shifter.config().analysis_window, window);
+
+    // windows for the new size are set first
+    shifter.set_analysis_window(WindowFunction::Hann);
+    assert_eq!(shifter.try_set_low_latency(true), Ok(()));
+    shifter.set_analysis_window(WindowFunction::Custom(WindowFunction::Hamming.coefficients(32)));
+    let config = shifter.config();
+    assert_eq!(PitchShifter::from_config(&config).config(), config);
+}
+
+#[test]
+#[should_panic(expected = "input_hop must hold exactly frame_size / over_sampling samples")]
+fn partial_hop() {
+    let mut shifter = PitchShifter::with_window_samples(256, 8000);
+    shifter.process_hop(8, 3.0, &[0.0; 31], &mut [0.0; 32]);
+}
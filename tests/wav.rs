@@ -0,0 +1,108 @@
+#![cfg(feature = "wav")]
+
+mod common;
+
+use common::sine;
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use pitch_shift::{shift_wav_file, PitchShifter, PitchShifterConfig};
+
+use std::path::PathBuf;
+
+/// A temporary file name no concurrent test run shares
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pitch_shift_{}_{}.wav", std::process::id(), name))
+}
+
+fn write_mono(path: &PathBuf, sample_rate: u32, samples: &[f32]) {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+    let mut writer = WavWriter::create(path, spec).unwrap();
+    for s in samples {
+        writer.write_sample(*s).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn wav_channels_are_shifted_separately() {
+    let input = temp_path("channels_input");
+    let output = temp_path("channels_output");
+    let spec = WavSpec {
+        channels: 2,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    // full scale on the left, which shifting pushes past it
+    let left: Vec<i16> = sine(250.0, 4000, 8000).iter().map(|s| (s * 32767.0) as i16).collect();
+    let right: Vec<i16> = sine(600.0, 4000, 8000).iter().map(|s| (s * 8000.0) as i16).collect();
+    let mut writer = WavWriter::create(&input, spec).unwrap();
+    for (l, r) in left.iter().zip(&right) {
+        writer.write_sample(*l).unwrap();
+        writer.write_sample(*r).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    // the frame is scaled to the file's sample rate
+    let config = PitchShifterConfig::new(64, 16000);
+    shift_wav_file(&input, &output, 5.0, &config).unwrap();
+
+    let mut reader = WavReader::open(&output).unwrap();
+    assert_eq!(reader.spec(), spec);
+    let shifted: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert_eq!(shifted.len(), 2 * left.len());
+
+    let mut shifter = PitchShifter::from_config(&PitchShifterConfig::new(64, 8000));
+    for (channel, in_b) in [left, right].iter().enumerate() {
+        let in_b: Vec<f32> = in_b.iter().map(|s| *s as f32 / 32768.0).collect();
+        let expected = shifter.process_clip(16, 5.0, &in_b);
+        for (i, expected) in expected.iter().enumerate() {
+            let expected = (expected * 32768.0).round().clamp(-32768.0, 32767.0) as i16;
+            assert_eq!(shifted[2 * i + channel], expected, "sample {} of channel {}", i, channel);
+        }
+    }
+}
+
+#[test]
+fn wav_frames_stay_powers_of_two() {
+    let input = temp_path("power_of_two_input");
+    let output = temp_path("power_of_two_output");
+    let in_b = sine(440.0, 8000, 44100);
+    write_mono(&input, 44100, &in_b);
+
+    // 2048 samples at 48 kHz would be 1881 at 44.1 kHz
+    let mut config = PitchShifterConfig::new(64, 48000);
+    config.frame_size = 2048;
+    shift_wav_file(&input, &output, 3.0, &config).unwrap();
+    let shifted: Vec<f32> = WavReader::open(&output).unwrap().samples::<f32>().map(Result::unwrap).collect();
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+
+    config.frame_size = 2048;
+    config.sample_rate = 44100;
+    let mut shifter = PitchShifter::from_config(&config);
+    assert_eq!(shifted, shifter.process_clip(16, 3.0, &in_b));
+}
+
+#[test]
+fn wav_rejects_windows_too_short_for_the_file() {
+    let input = temp_path("too_short_input");
+    let output = temp_path("too_short_output");
+    write_mono(&input, 8000, &sine(440.0, 1000, 8000));
+
+    // 8 samples at 48 kHz, one at 8 kHz
+    let mut config = PitchShifterConfig::new(64, 48000);
+    config.frame_size = 8;
+    config.over_sampling = 4;
+    let result = shift_wav_file(&input, &output, 3.0, &config);
+    std::fs::remove_file(&input).unwrap();
+    assert!(result.is_err());
+    assert!(!output.exists());
+}